@@ -13,6 +13,7 @@ import { AddDependencydialogbox } from "@/components/addDependencydialogbox";
 import { invoke } from "@tauri-apps/api/tauri";
 import { useCallback, useEffect, useRef, useState } from "react";
 import { useProjectAnalyzer } from "@/lib/projectDetails";
+import { formatError } from "@/lib/utils";
 import { Input } from "@/components/ui/input";
 import { UpdateDependencyDialog } from "@/components/updateDependencyDialog";
 import {
@@ -133,7 +134,7 @@ export default function Page() {
         );
       } catch (error) {
         console.error("Error running command:", error);
-        appendTerminalOutput(`Error running command: ${error}`);
+        appendTerminalOutput(`Error running command: ${formatError(error)}`);
       }
     }
   };
@@ -170,7 +171,7 @@ export default function Page() {
         });
       } catch (error) {
         console.error("Error installing dependency:", error);
-        appendTerminalOutput(`Error installing dependency: ${error}`);
+        appendTerminalOutput(`Error installing dependency: ${formatError(error)}`);
       }
     }
   };
@@ -230,7 +231,7 @@ export default function Page() {
         });
       } catch (error) {
         console.error("Error updating dependency:", error);
-        appendTerminalOutput(`Error updating dependency: ${error}`);
+        appendTerminalOutput(`Error updating dependency: ${formatError(error)}`);
       }
     }
   };
@@ -269,7 +270,7 @@ export default function Page() {
           });
         } catch (error) {
           console.error("Error deleting dependency:", error);
-          appendTerminalOutput(`Error deleting dependency: ${error}`);
+          appendTerminalOutput(`Error deleting dependency: ${formatError(error)}`);
         }
       }
     }
@@ -299,7 +300,7 @@ export default function Page() {
       localStorage.removeItem(projectName as string);
     } catch (error) {
       console.error("Error stopping command:", error);
-      appendTerminalOutput(`Error stopping command: ${error}`);
+      appendTerminalOutput(`Error stopping command: ${formatError(error)}`);
       appendTerminalOutput(`Looks like the process it terminated`);
       localStorage.removeItem(`${projectName}_terminalOutput`);
       localStorage.removeItem(projectName as string);
@@ -331,7 +332,7 @@ export default function Page() {
         });
       } catch (error) {
         console.error("Error reinstalling dependencies:", error);
-        appendTerminalOutput(`Error reinstalling dependencies: ${error}`);
+        appendTerminalOutput(`Error reinstalling dependencies: ${formatError(error)}`);
       }
     }
   };
@@ -358,7 +359,7 @@ export default function Page() {
         // Optionally, you can add more logic here, like redirecting the user
       } catch (error) {
         console.error("Error deleting site:", error);
-        appendTerminalOutput(`Error deleting site: ${error}`);
+        appendTerminalOutput(`Error deleting site: ${formatError(error)}`);
       } finally {
         setIsDeleting(false); // Set deleting state to false
       }
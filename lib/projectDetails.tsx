@@ -6,12 +6,35 @@ import { listen } from '@tauri-apps/api/event';
 import AnsiToHtml from 'ansi-to-html';
 import { useProjects } from "./useProject";
 
+interface FrameworkDetails {
+  name: string;
+  confidence: number;
+  dev_command: string | null;
+  build_command: string | null;
+  start_command: string | null;
+  default_port: number | null;
+}
+
 interface ProjectInfo {
   framework: string;
+  framework_details: FrameworkDetails | null;
+  workspace_root: string | null;
   runtime: string;
   packages: { name: string; version: string }[];
 }
 
+interface LogRecord {
+  pid: number;
+  project: string;
+  process: string;
+  timestamp: string;
+  stream: 'stdout' | 'stderr';
+  level: 'trace' | 'debug' | 'info' | 'warn' | 'error' | 'fatal';
+  text: string;
+  urls: string[];
+  port: number | null;
+}
+
 interface ProjectAnalyzerContextType {
   projectName: string;
   projectInfo: ProjectInfo | null;
@@ -120,15 +143,22 @@ export const ProjectAnalyzerProvider = ({ children }: { children: ReactNode }) =
     analyzeProject();
 
     // Set up event listener for project output
-    const unlisten = listen('project-output', (event: { payload: [number, string] }) => {
-      const [eventPid, output] = event.payload;
-      if (eventPid === pid) {
-        appendTerminalOutput(output);
+    const unlisten = listen('project-output', (event: { payload: LogRecord }) => {
+      if (event.payload.pid === pid) {
+        appendTerminalOutput(event.payload.text);
+      }
+    });
+
+    // The backend reports when a process stops or crashes on its own
+    const unlistenExit = listen('project-exited', (event: { payload: { pid: number } }) => {
+      if (event.payload.pid === pid) {
+        setIsRunning(false);
       }
     });
 
     return () => {
       unlisten.then(f => f());
+      unlistenExit.then(f => f());
     };
   }, [projectNameFromURL, pid]);
 
@@ -4,3 +4,23 @@ import { twMerge } from "tailwind-merge"
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
+
+// shape of the errors returned by the tauri commands
+export interface AppError {
+  code: string
+  message: string
+  hint?: string | null
+  context?: string | null
+  details?: unknown
+}
+
+export function isAppError(error: unknown): error is AppError {
+  return typeof error === "object" && error !== null && "code" in error && "message" in error
+}
+
+export function formatError(error: unknown): string {
+  if (isAppError(error)) {
+    return error.hint ? `${error.message}\n${error.hint}` : error.message
+  }
+  return typeof error === "string" ? error : String(error)
+}
@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde_json::Value;
+use crate::error::{AppError, ErrorCode};
+use crate::workspaces::is_workspace_root;
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+// prints where `bin` of `package` lives, run with .pnp.cjs loaded so yarn's
+// zip file system resolves it: node -e <script> <package> <bin>
+const PNP_RESOLVE_SCRIPT: &str = "const path = require('path'); \
+const manifest = require.resolve(process.argv[1] + '/package.json'); \
+const bin = require(manifest).bin; \
+const script = typeof bin === 'string' ? bin : (bin || {})[process.argv[2]]; \
+if (!script) process.exit(2); \
+console.log(path.join(path.dirname(manifest), script));";
+
+// a package binary to run as `node <node_args> <script> ...`
+pub struct ResolvedBin {
+    // e.g. --require .pnp.cjs for yarn plug'n'play
+    pub node_args: Vec<String>,
+    pub script: PathBuf,
+}
+
+// finds the script `bin` of `package` points to so it runs with the project's
+// node instead of relying on the shell shim. Looks in node_modules from the
+// project up to its workspace root, following package.json `bin` fields and
+// node_modules/.bin shims, or asks yarn's .pnp.cjs when there's no node_modules
+pub fn resolve_bin(project_path: &str, package: &str, bin: &str, node_program: &str) -> Result<ResolvedBin, AppError> {
+    let search_dirs = search_dirs(Path::new(project_path));
+
+    let node_modules: Vec<PathBuf> = search_dirs.iter().map(|dir| dir.join("node_modules")).filter(|dir| dir.is_dir()).collect();
+    for dir in &node_modules {
+        if let Some(script) = bin_from_manifest(&dir.join(package), bin).or_else(|| bin_from_shim(&dir.join(".bin"), bin)) {
+            return Ok(ResolvedBin { node_args: Vec::new(), script });
+        }
+    }
+
+    if let Some(pnp) = search_dirs.iter().map(|dir| dir.join(".pnp.cjs")).find(|pnp| pnp.exists()) {
+        return resolve_pnp_bin(&pnp, project_path, package, bin, node_program);
+    }
+
+    let error = if node_modules.is_empty() {
+        AppError::new(ErrorCode::DependenciesMissing, "The project's dependencies are not installed")
+    } else {
+        AppError::new(ErrorCode::DependenciesMissing, format!("{} is not installed", package))
+    };
+    Err(error
+        .with_hint(format!("Install the project's dependencies first, {} provides the `{}` command", package, bin))
+        .with_context(project_path.to_string()))
+}
+
+// the project and its parents up to and including the workspace root; all the
+// way up when the project isn't part of a workspace, like node's own lookup
+fn search_dirs(project_path: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for dir in project_path.ancestors() {
+        dirs.push(dir.to_path_buf());
+        if dir != project_path && is_workspace_root(dir) {
+            break;
+        }
+    }
+    dirs
+}
+
+// "bin": "cli.js" is named after the package, otherwise { name: path }
+fn bin_from_manifest(package_dir: &Path, bin: &str) -> Option<PathBuf> {
+    let manifest: Value = serde_json::from_str(&fs::read_to_string(package_dir.join("package.json")).ok()?).ok()?;
+    let package_name = manifest["name"].as_str().unwrap_or("");
+    let script = match &manifest["bin"] {
+        Value::String(script) if package_name.rsplit('/').next() == Some(bin) => script.as_str(),
+        Value::Object(bins) => bins.get(bin)?.as_str()?,
+        _ => return None,
+    };
+    let script = package_dir.join(script);
+    script.exists().then_some(script)
+}
+
+// npm and yarn link .bin entries to the script, pnpm and windows installs
+// write small shell / cmd wrappers that point at it
+fn bin_from_shim(bin_dir: &Path, bin: &str) -> Option<PathBuf> {
+    let shim = bin_dir.join(bin);
+    if fs::symlink_metadata(&shim).map(|metadata| metadata.file_type().is_symlink()).unwrap_or(false) {
+        return fs::canonicalize(&shim).ok();
+    }
+
+    for (path, markers) in [(shim, ["\"$basedir/", "\"$basedir\\"]), (bin_dir.join(format!("{}.cmd", bin)), ["\"%~dp0\\", "\"%dp0%\\"])] {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        // a copy of the script itself
+        if content.starts_with("#!") && content.lines().next().map(|line| line.contains("node")).unwrap_or(false) {
+            return Some(path);
+        }
+        if let Some(target) = shim_target(&content, &markers) {
+            let script = bin_dir.join(target);
+            if script.exists() {
+                return Some(script);
+            }
+        }
+    }
+    None
+}
+
+// the last path after one of `markers` that isn't the node executable the
+// shim prefers when there's one next to it
+fn shim_target(content: &str, markers: &[&str]) -> Option<String> {
+    let mut target = None;
+    for marker in markers {
+        for (index, _) in content.match_indices(marker) {
+            let rest = &content[index + marker.len()..];
+            let path = &rest[..rest.find('"')?];
+            if !path.is_empty() && path != "node" && path != "node.exe" {
+                target = Some(path.replace('\\', std::path::MAIN_SEPARATOR_STR));
+            }
+        }
+    }
+    target
+}
+
+// yarn plug'n'play keeps packages zipped in its cache; only node with
+// .pnp.cjs loaded can resolve and read them
+fn resolve_pnp_bin(pnp: &Path, project_path: &str, package: &str, bin: &str, node_program: &str) -> Result<ResolvedBin, AppError> {
+    let pnp_path = pnp.to_string_lossy().to_string();
+    let mut command = Command::new(node_program);
+    command.args(["--require", &pnp_path, "-e", PNP_RESOLVE_SCRIPT, package, bin]).current_dir(project_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = command.output().map_err(|e| AppError::io(e, "run node", Path::new(node_program)))?;
+    let script = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || script.is_empty() {
+        return Err(AppError::new(ErrorCode::DependenciesMissing, format!("{} is not installed", package))
+            .with_hint("Run `yarn install` to install the project's dependencies")
+            .with_context(pnp_path));
+    }
+
+    let mut node_args = vec!["--require".to_string(), pnp_path];
+    // esm packages need the loader as well
+    let loader = pnp.with_file_name(".pnp.loader.mjs");
+    if loader.exists() {
+        node_args.push("--experimental-loader".to_string());
+        node_args.push(loader.to_string_lossy().to_string());
+    }
+    Ok(ResolvedBin { node_args, script: PathBuf::from(script) })
+}
@@ -0,0 +1,313 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Duration, Local};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    KeyUsagePurpose, SanType,
+};
+use rustls::sign::CertifiedKey;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use crate::proxy::ProxyServer;
+use crate::utils::project_slug;
+use crate::error::{AppError, ErrorCode};
+
+const CA_NAME: &str = "dev-thing Development CA";
+const CA_VALID_DAYS: i64 = 10 * 365;
+// the longest validity macOS / iOS accept for a leaf certificate
+const LEAF_VALID_DAYS: i64 = 825;
+// leaves are reissued when they get this close to expiring
+const RENEW_BEFORE_DAYS: i64 = 30;
+
+// a local root CA in `dir/ca.pem` and one leaf per project in
+// `dir/<project slug>/`, used by the proxy to terminate TLS
+pub struct CertificateStore {
+    dir: PathBuf,
+    ca: Mutex<Option<Certificate>>,
+    // projects whose certificate was revoked, no new one is issued until it
+    // is regenerated explicitly
+    revoked: Mutex<HashSet<String>>,
+    // project -> (host, key) served by the proxy
+    keys: Mutex<HashMap<String, (String, Arc<CertifiedKey>)>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LeafCertificate {
+    project: String,
+    hosts: Vec<String>,
+    cert_path: String,
+    key_path: String,
+    issued_at: String,
+    expires_at: String,
+}
+
+#[derive(Serialize)]
+pub struct CertificateAuthorityInfo {
+    name: String,
+    cert_path: String,
+    created_at: Option<String>,
+}
+
+impl CertificateStore {
+    pub fn load(data_dir: &Path) -> CertificateStore {
+        let dir = data_dir.join("certs");
+        let revoked = fs::read_to_string(dir.join("revoked.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        CertificateStore {
+            dir,
+            ca: Mutex::new(None),
+            revoked: Mutex::new(revoked),
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn ca_path(&self) -> PathBuf {
+        self.dir.join("ca.pem")
+    }
+
+    fn ca_key_path(&self) -> PathBuf {
+        self.dir.join("ca-key.pem")
+    }
+
+    fn leaf_dir(&self, project: &str) -> PathBuf {
+        self.dir.join(project_slug(project))
+    }
+
+    // loads the CA from disk, creating it on first use
+    fn with_ca<R>(&self, f: impl FnOnce(&Certificate) -> Result<R, AppError>) -> Result<R, AppError> {
+        let mut ca = self.ca.lock().unwrap();
+        if ca.is_none() {
+            *ca = Some(self.load_or_create_ca()?);
+        }
+        f(ca.as_ref().unwrap())
+    }
+
+    fn load_or_create_ca(&self) -> Result<Certificate, AppError> {
+        let (cert_path, key_path) = (self.ca_path(), self.ca_key_path());
+        if cert_path.exists() && key_path.exists() {
+            let cert_pem = fs::read_to_string(&cert_path).map_err(|e| AppError::io(e, "read CA certificate", &cert_path))?;
+            let key_pem = fs::read_to_string(&key_path).map_err(|e| AppError::io(e, "read CA key", &key_path))?;
+            let invalid = |what: &str, e: rcgen::RcgenError, path: &Path| {
+                AppError::new(ErrorCode::InvalidInput, format!("Invalid {}: {}", what, e)).with_context(path.display().to_string())
+            };
+            let key_pair = KeyPair::from_pem(&key_pem).map_err(|e| invalid("CA key", e, &key_path))?;
+            let params = CertificateParams::from_ca_cert_pem(&cert_pem, key_pair).map_err(|e| invalid("CA certificate", e, &cert_path))?;
+            return Certificate::from_params(params).map_err(|e| invalid("CA certificate", e, &cert_path));
+        }
+
+        let mut params = CertificateParams::default();
+        params.distinguished_name = rcgen::DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, CA_NAME);
+        params.distinguished_name.push(DnType::OrganizationName, "dev-thing");
+        params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+        params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign, KeyUsagePurpose::DigitalSignature];
+        params.not_before = offset_time(Local::now() - Duration::days(1));
+        params.not_after = offset_time(Local::now() + Duration::days(CA_VALID_DAYS));
+        let ca = Certificate::from_params(params).map_err(|e| crypto_error("create CA", e))?;
+
+        fs::create_dir_all(&self.dir).map_err(|e| AppError::io(e, "create certificate directory", &self.dir))?;
+        let cert_pem = ca.serialize_pem().map_err(|e| crypto_error("create CA", e))?;
+        fs::write(&cert_path, cert_pem).map_err(|e| AppError::io(e, "save CA certificate", &cert_path))?;
+        write_private(&key_path, &ca.serialize_private_key_pem())?;
+        println!("Created development CA at {}", cert_path.display());
+        Ok(ca)
+    }
+
+    pub fn leaf(&self, project: &str) -> Option<LeafCertificate> {
+        fs::read_to_string(self.leaf_dir(project).join("meta.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    // the project's certificate if it covers `host` and isn't about to expire,
+    // otherwise a freshly issued one
+    pub fn ensure_leaf(&self, project: &str, host: &str) -> Result<LeafCertificate, AppError> {
+        if self.revoked.lock().unwrap().contains(project) {
+            return Err(AppError::new(ErrorCode::NotFound, format!("The certificate of {} was revoked", project))
+                .with_hint("Regenerate the certificate to use HTTPS"));
+        }
+        if let Some(leaf) = self.leaf(project) {
+            let renew_at = Local::now() + Duration::days(RENEW_BEFORE_DAYS);
+            let valid = DateTime::parse_from_rfc3339(&leaf.expires_at).map(|expires| expires > renew_at).unwrap_or(false);
+            if valid && leaf.hosts.iter().any(|covered| covered == host) && Path::new(&leaf.cert_path).exists() {
+                return Ok(leaf);
+            }
+        }
+        self.issue(project, host)
+    }
+
+    pub fn issue(&self, project: &str, host: &str) -> Result<LeafCertificate, AppError> {
+        let hosts = vec![host.to_string(), "localhost".to_string()];
+        let mut params = CertificateParams::new(hosts.clone());
+        params.subject_alt_names.push(SanType::IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        params.subject_alt_names.push(SanType::IpAddress(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+        params.distinguished_name = rcgen::DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, host);
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature, KeyUsagePurpose::KeyEncipherment];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params.use_authority_key_identifier_extension = true;
+        let issued_at = Local::now();
+        let expires_at = issued_at + Duration::days(LEAF_VALID_DAYS);
+        params.not_before = offset_time(issued_at - Duration::days(1));
+        params.not_after = offset_time(expires_at);
+
+        let cert = Certificate::from_params(params).map_err(|e| crypto_error("create certificate", e))?;
+        let cert_pem = self.with_ca(|ca| cert.serialize_pem_with_signer(ca).map_err(|e| crypto_error("sign certificate", e)))?;
+
+        let dir = self.leaf_dir(project);
+        fs::create_dir_all(&dir).map_err(|e| AppError::io(e, "create certificate directory", &dir))?;
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        fs::write(&cert_path, cert_pem).map_err(|e| AppError::io(e, "save certificate", &cert_path))?;
+        write_private(&key_path, &cert.serialize_private_key_pem())?;
+
+        let leaf = LeafCertificate {
+            project: project.to_string(),
+            hosts,
+            cert_path: cert_path.to_string_lossy().to_string(),
+            key_path: key_path.to_string_lossy().to_string(),
+            issued_at: issued_at.to_rfc3339(),
+            expires_at: expires_at.to_rfc3339(),
+        };
+        let meta = serde_json::to_string_pretty(&leaf).map_err(|e| e.to_string())?;
+        fs::write(dir.join("meta.json"), meta).map_err(|e| AppError::io(e, "save certificate", &dir))?;
+
+        self.keys.lock().unwrap().remove(project);
+        if self.revoked.lock().unwrap().remove(project) {
+            self.save_revoked()?;
+        }
+        Ok(leaf)
+    }
+
+    // deletes the project's key material and stops serving HTTPS for it
+    pub fn revoke(&self, project: &str) -> Result<(), AppError> {
+        self.keys.lock().unwrap().remove(project);
+        let dir = self.leaf_dir(project);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).map_err(|e| AppError::io(e, "delete certificate", &dir))?;
+        }
+        self.revoked.lock().unwrap().insert(project.to_string());
+        self.save_revoked()
+    }
+
+    fn save_revoked(&self) -> Result<(), AppError> {
+        fs::create_dir_all(&self.dir).map_err(|e| AppError::io(e, "create certificate directory", &self.dir))?;
+        let content = serde_json::to_string_pretty(&*self.revoked.lock().unwrap()).map_err(|e| e.to_string())?;
+        let path = self.dir.join("revoked.json");
+        fs::write(&path, content).map_err(|e| AppError::io(e, "save revoked certificates", &path))
+    }
+
+    // key and chain the proxy presents for `host`
+    pub fn certified_key(&self, project: &str, host: &str) -> Result<Arc<CertifiedKey>, AppError> {
+        if let Some((cached_host, key)) = self.keys.lock().unwrap().get(project) {
+            if cached_host == host {
+                return Ok(key.clone());
+            }
+        }
+
+        let leaf = self.ensure_leaf(project, host)?;
+        let mut chain = read_certs(Path::new(&leaf.cert_path))?;
+        chain.extend(read_certs(&self.ca_path())?);
+        let key_path = Path::new(&leaf.key_path);
+        let key_pem = fs::read(key_path).map_err(|e| AppError::io(e, "read key", key_path))?;
+        let invalid = |message: String| AppError::new(ErrorCode::InvalidInput, message).with_context(leaf.key_path.clone());
+        let key_der = rustls_pemfile::pkcs8_private_keys(&mut key_pem.as_slice())
+            .map_err(|e| invalid(format!("Invalid key: {}", e)))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| invalid("No private key in key file".to_string()))?;
+        let signing_key =
+            rustls::sign::any_supported_type(&rustls::PrivateKey(key_der)).map_err(|e| invalid(format!("Unsupported key: {}", e)))?;
+
+        let key = Arc::new(CertifiedKey::new(chain, signing_key));
+        self.keys.lock().unwrap().insert(project.to_string(), (host.to_string(), key.clone()));
+        Ok(key)
+    }
+}
+
+fn read_certs(path: &Path) -> Result<Vec<rustls::Certificate>, AppError> {
+    let pem = fs::read(path).map_err(|e| AppError::io(e, "read certificate", path))?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice()).map_err(|e| {
+        AppError::new(ErrorCode::InvalidInput, format!("Invalid certificate: {}", e)).with_context(path.display().to_string())
+    })?;
+    Ok(certs.into_iter().map(rustls::Certificate).collect())
+}
+
+fn crypto_error(action: &str, e: rcgen::RcgenError) -> AppError {
+    AppError::new(ErrorCode::Crypto, format!("Failed to {}: {}", action, e))
+}
+
+fn offset_time(time: DateTime<Local>) -> time::OffsetDateTime {
+    time::OffsetDateTime::from_unix_timestamp(time.timestamp()).unwrap_or(time::OffsetDateTime::UNIX_EPOCH)
+}
+
+// keys are only readable by the current user
+fn write_private(path: &Path, content: &str) -> Result<(), AppError> {
+    fs::write(path, content).map_err(|e| AppError::io(e, "save key", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|e| AppError::io(e, "protect key", path))?;
+    }
+    Ok(())
+}
+
+#[command]
+pub fn get_certificate_authority(state: State<'_, Arc<CertificateStore>>) -> Result<CertificateAuthorityInfo, AppError> {
+    state.with_ca(|_| Ok(()))?;
+    let cert_path = state.ca_path();
+    let created_at = fs::metadata(&cert_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(|modified| DateTime::<Local>::from(modified).to_rfc3339());
+
+    Ok(CertificateAuthorityInfo {
+        name: CA_NAME.to_string(),
+        cert_path: cert_path.to_string_lossy().to_string(),
+        created_at,
+    })
+}
+
+// copies the CA certificate to `destination` so it can be added to the
+// system / browser trust store, returns the written path
+#[command]
+pub fn export_ca_certificate(destination: String, state: State<'_, Arc<CertificateStore>>) -> Result<String, AppError> {
+    state.with_ca(|_| Ok(()))?;
+    let mut path = PathBuf::from(&destination);
+    if path.is_dir() {
+        path = path.join("dev-thing-ca.pem");
+    }
+    fs::copy(state.ca_path(), &path).map_err(|e| AppError::io(e, "export CA certificate", &path))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// the project's certificate for its proxy hostname, for frameworks that can
+// serve HTTPS themselves
+#[command]
+pub fn get_project_certificate(
+    project_path: String,
+    state: State<'_, Arc<CertificateStore>>,
+    proxy_state: State<'_, ProxyServer>,
+) -> Result<LeafCertificate, AppError> {
+    state.ensure_leaf(&project_path, &proxy_state.host_for(&project_path))
+}
+
+#[command]
+pub fn regenerate_project_certificate(
+    project_path: String,
+    state: State<'_, Arc<CertificateStore>>,
+    proxy_state: State<'_, ProxyServer>,
+) -> Result<LeafCertificate, AppError> {
+    state.issue(&project_path, &proxy_state.host_for(&project_path))
+}
+
+#[command]
+pub fn revoke_project_certificate(project_path: String, state: State<'_, Arc<CertificateStore>>) -> Result<(), AppError> {
+    state.revoke(&project_path)
+}
@@ -1,16 +1,26 @@
-use std::io::BufRead;
-use std::io::BufReader;
+use std::collections::HashMap;
 use std::path::Path;
 use std::fs;
 use std::env;
 use std::process::Command;
-use std::process::Stdio;
+use std::sync::Arc;
 use std::thread;
-use tauri::{command, State};
+use tauri::{command, Manager, State};
 use serde_json::Value;
-use crate::project_manager::terminate_process;
-use crate::utils::{execute_command, parse_command};
-use crate::project_manager::ProjectManager;
+use crate::utils::{resolve_executable, Argv};
+use crate::project_manager::{grace_period_from_ms, spawn_tracked, ProcessExit, ProcessSpec, ProjectManager};
+use crate::node_manager::{read_node_requirement, NodeManager, NodeResolution};
+use crate::port_manager::PortAllocator;
+use crate::frameworks::{self, FrameworkSummary, LaunchCommand};
+use crate::binaries::resolve_bin;
+use crate::workspaces::find_workspace_root;
+use crate::package_managers::{self, project_manifest};
+use crate::certificates::CertificateStore;
+use crate::mail_catcher::MailCatcher;
+use crate::env_manager::EnvManager;
+use crate::secrets::SecretStore;
+use crate::project_registry::ProjectRegistry;
+use crate::error::{AppError, ErrorCode};
 use tauri::api::path::home_dir;
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -18,8 +28,18 @@ use std::os::windows::process::CommandExt;
 #[derive(serde::Serialize)]
 pub struct ProjectInfo {
     framework: String,
+    // commands, default port and how sure the detection is
+    framework_details: Option<FrameworkSummary>,
+    // set for workspace roots and their members, see get_workspace
+    workspace_root: Option<String>,
     runtime: String,
     packages: Vec<Package>,
+    node_required: Option<String>,
+    node_required_source: Option<String>,
+    node_resolved: Option<String>,
+    // why no node could be resolved, e.g. an unknown lts/<codename> or an
+    // uninstalled pin; only start_project refuses to run because of it
+    node_warning: Option<AppError>,
 }
 
 #[derive(serde::Serialize)]
@@ -30,79 +50,40 @@ pub struct Package {
 
 // detecting framework and runtime
 #[command]
-pub fn analyze_project(path: String) -> Result<ProjectInfo, String> {
-    let package_json_path = Path::new(&path).join("package.json");
-    let package_json_content = fs::read_to_string(package_json_path)
-        .map_err(|e| format!("Failed to read package.json: {}", e))?;
+pub fn analyze_project(path: String, node_state: State<'_, NodeManager>) -> Result<ProjectInfo, AppError> {
+    analyze(&path, &node_state)
+}
 
-    let package_json: Value = serde_json::from_str(&package_json_content)
-        .map_err(|e| format!("Failed to parse package.json: {}", e))?;
+pub fn analyze(path: &str, node_state: &NodeManager) -> Result<ProjectInfo, AppError> {
+    let package_json = project_manifest(path)?;
 
-    let (framework, _command) = detect_framework(&package_json, "");
-    let runtime = detect_runtime_version(&path);
+    let detected = frameworks::detect(&package_json, path);
+    let runtime = package_managers::detect(path).name;
     let packages = extract_packages(&package_json);
+    let (node, node_warning) = match node_state.resolve(path) {
+        Ok(node) => (node, None),
+        Err(e) => {
+            println!("Can't resolve node for {}: {}", path, e);
+            (NodeResolution { required: read_node_requirement(path), resolved: None }, Some(e))
+        }
+    };
 
     Ok(ProjectInfo {
-        framework,
+        framework: detected.as_ref().map(|detected| detected.detector.name()).unwrap_or("Unknown").to_string(),
+        framework_details: detected.as_ref().map(FrameworkSummary::from),
+        workspace_root: find_workspace_root(path).map(|root| root.to_string_lossy().to_string()),
         runtime,
         packages,
+        node_required: node.required.as_ref().map(|required| required.spec.clone()),
+        node_required_source: node.required.map(|required| required.source),
+        node_resolved: node.resolved.map(|resolved| resolved.version),
+        node_warning,
     })
 }
-// yarn plug and play doesn't have node modules folder
-pub fn detect_framework(package_json: &Value, path: &str) -> (String, String) {
-    if package_json["dependencies"].get("next").is_some() {
-        let path_to_dev;
-        if cfg!(target_os = "windows") {
-            path_to_dev = format!("\"{}\\node_modules\\next\\dist\\bin\\next\" dev", path)
-        }
-        else {
-            path_to_dev = format!("\"{}/node_modules/next/dist/bin/next\" dev", path)
-        }
-        ("Next.js".to_string(), format!("node {}", path_to_dev).to_string())
-    } else if package_json["dependencies"].get("react").is_some() {
-        let path_to_dev;
-        if cfg!(target_os = "windows") {
-            path_to_dev = format!("\"{}\\node_modules\\react-scripts\\scripts\\start.js\"", path)
-        }
-        else {
-            path_to_dev = format!("\"{}/node_modules/react-scripts/scripts/start.js\"", path)
-        }
-        ("React".to_string(), format!("node {}", path_to_dev).to_string())
-    }  else if  package_json["dependencies"].get("nuxt").is_some(){
-        let path_to_dev;
-        if cfg!(target_os = "windows") {
-            path_to_dev = format!("\"{}\\node_modules\\nuxt\\bin\\nuxt.mjs\" dev", path)
 
-        }
-        else {
-            path_to_dev = format!("\"{}/node_modules/nuxt/bin/nuxt.mjs\" dev", path)
-
-        }
-        ("Nuxt.js".to_string(), format!("node {}", path_to_dev).to_string())
-    }    
-     else if package_json["dependencies"].get("vue").is_some() {
-        let path_to_dev;
-        if cfg!(target_os = "windows") {
-            path_to_dev = format!("\"{}\\node_modules\\vite\\dist\\node\\cli.js\"", path)
-        }
-        else {
-            path_to_dev = format!("\"{}/node_modules/vite/dist/node/cli.js\"", path)
-        }
-        ("Vue.js".to_string(), format!("node {}", path_to_dev).to_string())
-    }
-     else {
-        ("Unknown".to_string(), "".to_string())
-    }
-}
-
-pub fn detect_runtime_version(path: &str) -> String {
-    if Path::new(path).join("pnpm-lock.yaml").exists() {
-        "pnpm".to_string()
-    } else if Path::new(path).join("yarn.lock").exists() {
-        "yarn".to_string()
-    } else {
-        "npm".to_string()
-    }
+// bun and deno run javascript themselves instead of the project's node
+pub fn runs_on_node(runtime: &str) -> bool {
+    runtime != "bun" && runtime != "deno"
 }
 
 pub fn extract_packages(package_json: &Value) -> Vec<Package> {
@@ -130,20 +111,7 @@ pub fn extract_packages(package_json: &Value) -> Vec<Package> {
 }
 
 #[command]
-pub fn detect_runtime(project_path: String) -> Result<String, String> {
-    if fs::metadata(format!("{}/pnpm-lock.yaml", project_path)).is_ok() {
-        Ok("pnpm".to_string())
-    } else if fs::metadata(format!("{}/yarn.lock", project_path)).is_ok() {
-        Ok("yarn".to_string())
-    } else if fs::metadata(format!("{}/package-lock.json", project_path)).is_ok() {
-        Ok("npm".to_string())
-    } else {
-        Err("No supported package manager lock file found".to_string())
-    }
-}
-
-#[command]
-pub fn launch_ide(project_path: String, ide: String) -> Result<(), String> {
+pub fn launch_ide(project_path: String, ide: String) -> Result<(), AppError> {
     let os = env::consts::OS;
 
     match os {
@@ -154,7 +122,7 @@ pub fn launch_ide(project_path: String, ide: String) -> Result<(), String> {
                     .args(&["/C", &ide, &project_path])
                     .creation_flags(0x08000000) // CREATE_NO_WINDOW
                     .spawn()
-                    .map_err(|e| format!("Failed to launch {}: {}", ide, e))?;
+                    .map_err(|e| launch_error(e, &ide))?;
             }       
             
         },
@@ -167,10 +135,10 @@ pub fn launch_ide(project_path: String, ide: String) -> Result<(), String> {
             
             command.arg(&project_path)
                 .spawn()
-                .map_err(|e| format!("Failed to launch {}: {}", ide, e))?;
+                .map_err(|e| launch_error(e, &ide))?;
         },
         _ => {
-            return Err("Unsupported operating system".into());
+            return Err(AppError::unsupported_platform());
         }
     }
 
@@ -178,7 +146,7 @@ pub fn launch_ide(project_path: String, ide: String) -> Result<(), String> {
 }
 
 #[command]
-pub fn open_file_explorer(project_path: String) -> Result<(), String> {
+pub fn open_file_explorer(project_path: String) -> Result<(), AppError> {
     let os = env::consts::OS;
     
     match os {
@@ -189,14 +157,14 @@ pub fn open_file_explorer(project_path: String) -> Result<(), String> {
                     .arg(&project_path)
                 .creation_flags(0x08000000) // CREATE_NO_WINDOW
                     .spawn()
-                    .map_err(|e| format!("Failed to open file explorer: {}", e))?;
+                    .map_err(|e| AppError::io(e, "open file explorer", Path::new(&project_path)))?;
             }
         },
         "macos" => {
             Command::new("open")
                 .args(["-R", &project_path])
                 .spawn()
-                .map_err(|e| format!("Failed to open file explorer: {}", e))?;
+                .map_err(|e| AppError::io(e, "open file explorer", Path::new(&project_path)))?;
         },
         "linux" => {
             // Try xdg-open first, fall back to dbus-send
@@ -214,11 +182,11 @@ pub fn open_file_explorer(project_path: String) -> Result<(), String> {
                         "string:\"\""
                     ])
                     .spawn()
-                    .map_err(|e| format!("Failed to open file explorer: {}", e))?;
+                    .map_err(|e| AppError::io(e, "open file explorer", Path::new(&project_path)))?;
             }
         },
         _ => {
-            return Err("Unsupported operating system".into());
+            return Err(AppError::unsupported_platform());
         }
     }
 
@@ -226,7 +194,8 @@ pub fn open_file_explorer(project_path: String) -> Result<(), String> {
 }
 
 #[command]
-pub fn start_project_creation(window: tauri::Window, runtime: String, framework: String, project_name: String, location: String) -> Result<(), String> {
+pub fn start_project_creation(window: tauri::Window, runtime: String, framework: String, project_name: String, location: String) -> Result<(), AppError> {
+    validate_project_name(&project_name)?;
     let create_path;
     if cfg!(target_os = "windows") {
          create_path = format!("{}\\{}", location, project_name);
@@ -236,82 +205,60 @@ pub fn start_project_creation(window: tauri::Window, runtime: String, framework:
     thread::spawn(move || {
         window.emit("creation_status", "Starting project creation...").unwrap();
         
+        let next_flags = ["--ts", "--eslint", "--tailwind", "--src-dir", "--app", "--no-import-alias"];
+        let vue_flags = ["--typescript", "--eslint-with-prettier"];
         let cmd = match (runtime.as_str(), framework.as_str()) {
             ("pnpm", "next.js") => {
-                vec!["pnpm", "dlx", "create-next-app", &create_path, "--ts", "--eslint", "--tailwind", "--src-dir", "--app", "--no-import-alias"]
+                Argv::new("pnpm").args(["dlx", "create-next-app", create_path.as_str()]).args(next_flags)
             }
             ("npm", "next.js") => {
-                vec!["npx", "create-next-app@latest", &create_path, "--yes","--ts", "--eslint", "--tailwind", "--src-dir", "--app", "--no-import-alias"]
+                Argv::new("npx").args(["create-next-app@latest", create_path.as_str(), "--yes"]).args(next_flags)
             }
             ("yarn", "next.js") => {
-                vec!["yarn", "create","next-app", &create_path, "--ts", "--eslint", "--tailwind", "--src-dir", "--app", "--no-import-alias"]
-            }
-            ("pnpm", "vue") => {
-                vec!["pnpm", "create", "vue@latest", &project_name, "--typescript", "--eslint-with-prettier"]
+                Argv::new("yarn").args(["create", "next-app", create_path.as_str()]).args(next_flags)
             }
-            ("npm", "vue") => {
-                vec!["npm", "create", "vue@latest", &project_name,"--typescript", "--eslint-with-prettier"]
+            ("bun", "next.js") => {
+                Argv::new("bun").args(["create", "next-app", create_path.as_str(), "--use-bun"]).args(next_flags)
             }
-            ("yarn", "vue") => {
-                vec!["yarn", "dlx", "create-vue@latest", &project_name,"--typescript", "--eslint-with-prettier"]
-            }
-            ("pnpm", "nuxt") => {
-                vec![ "pnpm", "dlx","nuxi@latest", "init",&project_name, "--gitInit","--packageManager","pnpm"]
-            }
-            ("npm", "nuxt") => {
-                vec![ "npx", "nuxi@latest", "init", &project_name, "--gitInit","--packageManager","npm"]
-            }
-            ("yarn", "nuxt") => {
-                vec!["yarn", "dlx", "nuxi@latest", "init", &project_name, "--gitInit","--packageManager","yarn"]
-            }
-            _ => {
-                window.emit("creation_status", "Error: Unsupported runtime or frameworks").unwrap();
-                return;
-            }
-        };
-        let cmd2 = match (runtime.as_str(), framework.as_str()) {
-            ("pnpm", "next.js") => {
-                format!("pnpm dlx create-next-app {} --ts --eslint --tailwind --src-dir --app --no-import-alias", project_name)
-            }
-            ("npm", "next.js") => {
-                format!("npx create-next-app {} --ts --eslint --tailwind --src-dir --app --no-import-alias", project_name)
-            }
-            ("yarn", "next.js") => {
-                format!("yarn create next-app {} --ts --eslint --tailwind --src-dir --app --no-import-alias", project_name)
+            ("deno", "next.js") => {
+                Argv::new("deno").args(["run", "-A", "npm:create-next-app@latest", create_path.as_str(), "--yes"]).args(next_flags)
             }
             ("pnpm", "vue") => {
-                format!("pnpm create vue@latest {} --typescript --eslint-with-prettier", project_name)
+                Argv::new("pnpm").args(["create", "vue@latest", project_name.as_str()]).args(vue_flags)
             }
             ("npm", "vue") => {
-                format!("npm create vue@latest {} --typescript --eslint-with-prettier", project_name)
+                Argv::new("npm").args(["create", "vue@latest", project_name.as_str()]).args(vue_flags)
             }
             ("yarn", "vue") => {
-                format!("yarn dlx create-vue@latest {} --typescript --eslint-with-prettier", project_name)
+                Argv::new("yarn").args(["dlx", "create-vue@latest", project_name.as_str()]).args(vue_flags)
+            }
+            ("bun", "vue") => {
+                Argv::new("bun").args(["create", "vue@latest", project_name.as_str()]).args(vue_flags)
+            }
+            ("deno", "vue") => {
+                Argv::new("deno").args(["run", "-A", "npm:create-vue@latest", project_name.as_str()]).args(vue_flags)
             }
             ("pnpm", "nuxt") => {
-                format!("pnpm dlx nuxi@latest init {}  --gitInit --packageManager pnpm", project_name)
+                Argv::new("pnpm").args(["dlx", "nuxi@latest", "init", project_name.as_str(), "--gitInit", "--packageManager", "pnpm"])
             }
             ("npm", "nuxt") => {
-                format!("npx nuxi@latest init {}  --gitInit --packageManager npm", project_name)
+                Argv::new("npx").args(["nuxi@latest", "init", project_name.as_str(), "--gitInit", "--packageManager", "npm"])
             }
             ("yarn", "nuxt") => {
-                format!("yarn dlx nuxi@latest init {}  --gitInit --packageManager yarn", project_name)
+                Argv::new("yarn").args(["dlx", "nuxi@latest", "init", project_name.as_str(), "--gitInit", "--packageManager", "yarn"])
+            }
+            ("bun", "nuxt") => {
+                Argv::new("bunx").args(["nuxi@latest", "init", project_name.as_str(), "--gitInit", "--packageManager", "bun"])
+            }
+            ("deno", "nuxt") => {
+                Argv::new("deno").args(["run", "-A", "npm:nuxi@latest", "init", project_name.as_str(), "--gitInit", "--packageManager", "deno"])
             }
             _ => {
-                window.emit("creation_status", "Error: Unsupported runtime or framework").unwrap();
+                window.emit("creation_status", "Error: Unsupported runtime or frameworks").unwrap();
                 return;
             }
         };
-        println!("Command: {:?}", cmd);
-        let output;
-        if cfg!(target_os = "windows") {
-            println!("Command: {:?}", cmd);
-            output = execute_command(&cmd, &location);
-        }
-        else {
-            println!("Command2: {:?}", cmd2);
-            output = execute_command(&[&cmd2], &location);
-        }
+        let output = cmd.output(&location, &HashMap::new());
         
         
         match output {
@@ -319,17 +266,20 @@ pub fn start_project_creation(window: tauri::Window, runtime: String, framework:
                 if output.status.success() {
                     if framework == "next.js" || framework == "nuxt"  {
                         println!("Project created successfully!");
+                        register_created_project(&window, &create_path);
                         window.emit("creation_status", "Project created successfully!").unwrap();
                     }
                     else
                     if framework == "vue" {
-                        let install_cmd = format!("{} i", runtime);
+                        let install_cmd = Argv::new(&runtime).arg("install");
                         print!("path is {}",create_path);
-                        let install_output = execute_command(&[&install_cmd], &create_path);
+                        let envs = window.state::<EnvManager>().project_env(&create_path).unwrap_or_default();
+                        let install_output = install_cmd.output(&create_path, &envs);
                         match install_output {
                             Ok(install_output) => {
                                 if install_output.status.success() {
                                     println!("Dependencies installed successfully!");
+                                    register_created_project(&window, &create_path);
                                     window.emit("creation_status", "Project created successfully!").unwrap();
                                 } else {
                                     let error_message = String::from_utf8_lossy(&install_output.stderr);
@@ -358,104 +308,273 @@ pub fn start_project_creation(window: tauri::Window, runtime: String, framework:
     Ok(())
 }
 
+// the name becomes a folder and a generator argument, so it can't contain
+// path separators or look like a flag
+fn validate_project_name(name: &str) -> Result<(), AppError> {
+    let valid = !name.is_empty()
+        && !name.starts_with(['-', '.'])
+        && name.chars().all(|c| c.is_alphanumeric() || "-_.".contains(c));
+    if !valid {
+        return Err(AppError::new(ErrorCode::InvalidInput, format!("Invalid project name \"{}\"", name))
+            .with_hint("Use letters, numbers, - and _, not starting with - or ."));
+    }
+    Ok(())
+}
+
+fn register_created_project(window: &tauri::Window, path: &str) {
+    let node_state = window.state::<NodeManager>();
+    if let Err(e) = window.state::<ProjectRegistry>().add(path, &node_state) {
+        println!("Failed to register {}: {}", path, e);
+    }
+}
+
 #[command]
-pub fn delete_site(project_path: String) -> Result<(), String> {
+pub fn delete_site(project_path: String, registry_state: State<'_, ProjectRegistry>) -> Result<(), AppError> {
     let path = Path::new(&project_path);
     if path.exists() {
-        fs::remove_dir_all(path).map_err(|e| format!("Failed to delete site: {}", e))?;
-        Ok(())
+        fs::remove_dir_all(path).map_err(|e| AppError::io(e, "delete site", path))?;
+        Ok(registry_state.remove_path(&project_path)?)
     } else {
-        Err("Project path does not exist".to_string())
+        Err(AppError::new(ErrorCode::NotFound, "Project path does not exist").with_context(project_path))
     }
 }
 
-#[command]
-pub fn start_project(
-    project_path: String,
-    window: tauri::Window,
-    state: State<'_, ProjectManager>,
-) -> Result<u32, String> {
-    // Read package.json
-    let package_json_path;
-    if cfg!(target_os = "windows") {
-        package_json_path = format!("{}\\package.json", project_path);
+fn launch_error(error: std::io::Error, ide: &str) -> AppError {
+    let message = format!("Failed to launch {}: {}", ide, error);
+    match error.kind() {
+        std::io::ErrorKind::NotFound => AppError::new(ErrorCode::NotFound, message)
+            .with_hint(format!("Make sure {} is installed and its command is on PATH", ide)),
+        std::io::ErrorKind::PermissionDenied => AppError::new(ErrorCode::PermissionDenied, message),
+        _ => AppError::new(ErrorCode::Io, message),
     }
-    else {
-        package_json_path = format!("{}/package.json", project_path);
-    }
-    let package_json: Value = serde_json::from_str(&std::fs::read_to_string(package_json_path).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+}
 
-    let (_framework, command) = detect_framework(&package_json, &project_path);
-    
-    if command.is_empty() {
-        return Err("Unsupported framework".to_string());
+#[command]
+pub fn start_project(project_path: String, window: tauri::Window) -> Result<u32, AppError> {
+    start_dev_server(&project_path, &window)
+}
+
+// the project's dev server with its own port, env files, secrets, mail
+// catcher and certificates; workspace apps are started the same way
+pub fn start_dev_server(project_path: &str, window: &tauri::Window) -> Result<u32, AppError> {
+    let state = window.state::<ProjectManager>();
+    let node_state = window.state::<NodeManager>();
+    let port_state = window.state::<PortAllocator>();
+    let certificate_state = window.state::<Arc<CertificateStore>>();
+    let mail_state = window.state::<MailCatcher>();
+    let env_state = window.state::<EnvManager>();
+    let secret_state = window.state::<SecretStore>();
+    let registry_state = window.state::<ProjectRegistry>();
+
+    let package_json = project_manifest(project_path)?;
+
+    let unsupported = || {
+        AppError::new(ErrorCode::UnsupportedFramework, "Unsupported framework")
+            .with_hint("Add the dev command as a script and start it from the scripts list instead")
+            .with_context(project_path)
+    };
+    let detector = frameworks::detect(&package_json, project_path).ok_or_else(unsupported)?.detector;
+    let command = detector.dev_command().ok_or_else(unsupported)?;
+
+    let runtime = package_managers::detect(project_path).name;
+    // check the project's node requirement before spawning anything
+    let node = if runs_on_node(&runtime) { node_state.project_node(project_path)? } else { None };
+    // .env files and overrides first, so PATH and the injected PORT / SMTP
+    // settings below win over them
+    let mut envs = env_state.project_env(project_path)?;
+    // decrypted only now and masked in everything the process prints
+    let masked = secret_state.inject(project_path, &mut envs)?;
+    envs.extend(node_state.command_env(project_path)?);
+    let node_program = node.map(|install| install.path).unwrap_or("node".to_string());
+    let (executable, mut args) = launch_args(project_path, &runtime, &package_json, command, node_program, &envs)?;
+
+    // a port of its own so projects don't fight over the framework default,
+    // checked first so a running dev server doesn't get its port reassigned
+    if state.is_running(project_path, "dev") {
+        return Err(AppError::new(ErrorCode::AlreadyRunning, "The dev server is already running"));
+    }
+    // desktop apps (electron) don't serve anything
+    let port = match detector.default_port() {
+        Some(default_port) => {
+            let port = port_state.allocate(project_path, Some(default_port))?;
+            let (port_args, port_envs) = detector.port_overrides(port);
+            // npm only passes arguments after `--` on to the script
+            if matches!(command, LaunchCommand::Script(_)) && !port_args.is_empty() && executable_name(&executable) == "npm" {
+                args.push("--".to_string());
+            }
+            args.extend(port_args);
+            envs.extend(port_envs);
+            Some(port)
+        }
+        None => None,
+    };
+    // mail sent by the app ends up in the project's mail catcher
+    let smtp_port = mail_state.ensure_server(project_path, window.clone())?;
+    envs.insert("SMTP_HOST".to_string(), "127.0.0.1".to_string());
+    envs.insert("SMTP_PORT".to_string(), smtp_port.to_string());
+    // node's own fetch / https clients trust the proxy's certificates
+    let ca_path = certificate_state.ca_path();
+    if ca_path.exists() {
+        envs.insert("NODE_EXTRA_CA_CERTS".to_string(), ca_path.to_string_lossy().to_string());
     }
 
-    let (executable, args) = parse_command(&command)?;
+    println!("Command: {} {} (port {:?})", executable, args.join(" "), port);
+    let spec = ProcessSpec {
+        program: executable,
+        args,
+        cwd: project_path.to_string(),
+        envs,
+        port,
+        masked,
+    };
 
-    println!("Command: {}", command);
-    // Spawn the child process
-    let mut command = Command::new(executable);
-        command.args(&args)
-            .current_dir(&project_path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+    let pid = spawn_tracked(project_path, "dev", spec, window.clone(), &state)?;
+    registry_state.touch(project_path);
+    Ok(pid)
+}
 
-        #[cfg(target_os = "windows")]
-        {
-            use std::os::windows::process::CommandExt;
-            command.creation_flags(0x08000000);
+// program and arguments for a framework command: binaries run with the
+// project's node (or bun / deno), scripts through the package manager
+fn launch_args(
+    project_path: &str,
+    runtime: &str,
+    package_json: &Value,
+    command: LaunchCommand,
+    node_program: String,
+    envs: &HashMap<String, String>,
+) -> Result<(String, Vec<String>), AppError> {
+    let runtime_program = || resolve_executable(runtime, project_path, envs).map_err(|_| AppError::package_manager_missing(runtime));
+    match command {
+        LaunchCommand::Bin { package, bin, args } => {
+            let args = args.iter().map(|arg| arg.to_string());
+            let resolved = resolve_bin(project_path, package, bin, &node_program);
+            let (program, mut launch_args) = match (runtime, resolved) {
+                ("bun", resolved) => (runtime_program()?, vec![resolved?.script.to_string_lossy().to_string()]),
+                ("deno", Ok(resolved)) => (runtime_program()?, vec!["run".to_string(), "-A".to_string(), resolved.script.to_string_lossy().to_string()]),
+                // without node_modules deno runs the package from its own cache
+                ("deno", Err(e)) if e.code == ErrorCode::DependenciesMissing => {
+                    let specifier = if package.rsplit('/').next() == Some(bin) { format!("npm:{}", package) } else { format!("npm:{}/{}", package, bin) };
+                    (runtime_program()?, vec!["run".to_string(), "-A".to_string(), specifier])
+                }
+                (_, resolved) => {
+                    let resolved = resolved?;
+                    let mut launch_args = resolved.node_args;
+                    launch_args.push(resolved.script.to_string_lossy().to_string());
+                    (node_program, launch_args)
+                }
+            };
+            launch_args.extend(args);
+            Ok((program, launch_args))
         }
+        LaunchCommand::Script(candidates) => {
+            let script = candidates
+                .iter()
+                .find(|script| package_json["scripts"].get(**script).is_some())
+                .ok_or_else(|| {
+                    AppError::new(ErrorCode::NotFound, format!("package.json has no {} script", candidates.join(" or ")))
+                        .with_hint("Add a script that starts the server")
+                        .with_context(project_path.to_string())
+                })?;
+            Ok((runtime_program()?, vec![script_verb(runtime).to_string(), script.to_string()]))
+        }
+    }
+}
 
-let mut child = command.spawn().map_err(|e| e.to_string())?;
+// `deno task dev`, `<package manager> run dev` for the others
+fn script_verb(runtime: &str) -> &'static str {
+    if runtime == "deno" {
+        "task"
+    } else {
+        "run"
+    }
+}
 
-    let pid = child.id();
+fn executable_name(path: &str) -> String {
+    Path::new(path).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default()
+}
 
-    // Stream output
-    let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
-    let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
-    
-    println!("stdout: {:?}", stdout);
-    println!("stderr: {:?}", stderr);
-    let window_clone = window.clone();
+#[derive(serde::Serialize)]
+pub struct Script {
+    name: String,
+    command: String,
+}
 
-    thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                window.emit("project-output", (pid, line)).unwrap();
-            }
-        }
-    });
+#[command]
+pub fn list_scripts(project_path: String) -> Result<Vec<Script>, AppError> {
+    let package_json = project_manifest(&project_path)?;
+
+    let scripts = package_json["scripts"]
+        .as_object()
+        .map(|scripts| {
+            scripts
+                .iter()
+                .map(|(name, command)| Script {
+                    name: name.clone(),
+                    command: command.as_str().unwrap_or("").to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(scripts)
+}
 
-    thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                window_clone.emit("project-error", (pid, line)).unwrap();
-            }
-        }
-    });
-    
-    // Store the child process
-    state.0.lock().unwrap().insert(pid, child);
-    
-    Ok(pid)
+// runs a package.json script through the project's package manager,
+// tracked and streamed the same way as the dev server
+#[command]
+pub fn run_script(
+    project_path: String,
+    script: String,
+    window: tauri::Window,
+    state: State<'_, ProjectManager>,
+    node_state: State<'_, NodeManager>,
+    env_state: State<'_, EnvManager>,
+    secret_state: State<'_, SecretStore>,
+) -> Result<u32, AppError> {
+    let scripts = list_scripts(project_path.clone())?;
+    if !scripts.iter().any(|s| s.name == script) {
+        return Err(AppError::new(ErrorCode::NotFound, format!("Script \"{}\" not found in package.json", script))
+            .with_context(project_path));
+    }
+
+    let runtime = package_managers::detect(&project_path).name;
+    let mut envs = env_state.project_env(&project_path)?;
+    let masked = secret_state.inject(&project_path, &mut envs)?;
+    envs.extend(node_state.command_env(&project_path)?);
+    let executable = resolve_executable(&runtime, &project_path, &envs)
+        .map_err(|_| AppError::package_manager_missing(&runtime))?;
+
+    println!("Running script: {} {} {}", runtime, script_verb(&runtime), script);
+    let spec = ProcessSpec {
+        program: executable,
+        args: vec![script_verb(&runtime).to_string(), script.clone()],
+        cwd: project_path.clone(),
+        envs,
+        port: None,
+        masked,
+    };
+
+    spawn_tracked(&project_path, &script, spec, window, &state)
 }
 
+// reports how the process ended: `stopped` when it was running until now,
+// `crashed` / `exited` when it had already gone away on its own
 #[command]
-pub fn close_project(state: State<'_, ProjectManager>, pid: u32) -> Result<(), String> {
-    let mut projects = state.0.lock().unwrap();
-    if projects.remove(&pid).is_some() {
-        terminate_process(pid)?;
+pub fn close_project(state: State<'_, ProjectManager>, pid: u32, grace_period_ms: Option<u64>) -> Result<Option<ProcessExit>, AppError> {
+    if let Some((project, name)) = state.find_by_pid(pid) {
+        state.stop(&project, &name, grace_period_from_ms(grace_period_ms))?;
     }
-    Ok(())
+    Ok(state.recent_exit(pid))
+}
+
+fn no_home_dir() -> AppError {
+    AppError::new(ErrorCode::NotFound, "Could not determine home directory").with_hint("Choose a folder for the projects instead")
 }
 
 //create local projects folder incase of new root folder name
 #[command]
-pub fn create_local_projects_folder(folder_name: Option<String>, path: Option<String>) -> Result<String, String> {
-    let home_path = home_dir().ok_or("Could not determine home directory")?;
+pub fn create_local_projects_folder(folder_name: Option<String>, path: Option<String>) -> Result<String, AppError> {
+    let home_path = home_dir().ok_or_else(no_home_dir)?;
     let projects_path = match (folder_name, path) {
         (Some(name), Some(p)) => Path::new(&p).join(name),
         (Some(name), None) => home_path.join(name),
@@ -464,7 +583,7 @@ pub fn create_local_projects_folder(folder_name: Option<String>, path: Option<St
     };
 
     if !projects_path.exists() {
-        fs::create_dir_all(&projects_path).map_err(|err| err.to_string())?;
+        fs::create_dir_all(&projects_path).map_err(|e| AppError::io(e, "create projects folder", &projects_path))?;
     }
 
     Ok(projects_path.to_str().unwrap().to_string())
@@ -472,8 +591,8 @@ pub fn create_local_projects_folder(folder_name: Option<String>, path: Option<St
 
 //update project path currently not in use
 #[command]
-pub fn update_project_path(new_path: String) -> Result<String, String> {
-    let home_path = home_dir().ok_or("Could not determine home directory")?;
+pub fn update_project_path(new_path: String) -> Result<String, AppError> {
+    let home_path = home_dir().ok_or_else(no_home_dir)?;
     let projects_path = if new_path.is_empty() {
         home_path.join("Local-Projects")
     } else {
@@ -481,7 +600,7 @@ pub fn update_project_path(new_path: String) -> Result<String, String> {
     };
 
     if !projects_path.exists() {
-        fs::create_dir_all(&projects_path).map_err(|err| err.to_string())?;
+        fs::create_dir_all(&projects_path).map_err(|e| AppError::io(e, "create projects folder", &projects_path))?;
     }
 
     Ok(projects_path.to_str().unwrap().to_string())
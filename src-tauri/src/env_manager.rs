@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::Serialize;
+use tauri::{command, State};
+use crate::error::{AppError, ErrorCode};
+
+// lowest precedence first, the order Next.js, Vite and dotenv-flow load them
+// in for the development mode
+const ENV_FILES: [&str; 4] = [".env", ".env.development", ".env.local", ".env.development.local"];
+// source of the values stored in dev-thing instead of the repo
+const OVERRIDE_SOURCE: &str = "dev-thing";
+
+// source name and the variables it defines
+type EnvLayer = (String, BTreeMap<String, String>);
+
+// project path -> variables set in dev-thing, persisted to `file` so they
+// never end up in the project's repository
+pub struct EnvManager {
+    pub file: PathBuf,
+    pub overrides: Mutex<HashMap<String, BTreeMap<String, String>>>,
+}
+
+#[derive(Serialize)]
+pub struct EnvVar {
+    key: String,
+    value: String,
+    // file name, or "dev-thing" for overrides
+    source: String,
+    // lower precedence sources that also define the key
+    shadowed: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct EnvFile {
+    name: String,
+    exists: bool,
+    vars: BTreeMap<String, String>,
+}
+
+impl EnvManager {
+    pub fn load(data_dir: &Path) -> EnvManager {
+        let file = data_dir.join("env-overrides.json");
+        let overrides = fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        EnvManager {
+            file,
+            overrides: Mutex::new(overrides),
+        }
+    }
+
+    fn save(&self, overrides: &HashMap<String, BTreeMap<String, String>>) -> Result<(), AppError> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(e, "create data directory", parent))?;
+        }
+        let content = serde_json::to_string_pretty(overrides).map_err(|e| e.to_string())?;
+        fs::write(&self.file, content).map_err(|e| AppError::io(e, "save environment overrides", &self.file))
+    }
+
+    // every source in precedence order, lowest first
+    fn layers(&self, project: &str) -> Result<Vec<EnvLayer>, AppError> {
+        let mut layers = Vec::new();
+        for name in ENV_FILES {
+            let path = Path::new(project).join(name);
+            if path.exists() {
+                let content = fs::read_to_string(&path).map_err(|e| AppError::io(e, &format!("read {}", name), &path))?;
+                layers.push((name.to_string(), parse_env(&content)));
+            }
+        }
+        let overrides = self.overrides.lock().unwrap().get(project).cloned().unwrap_or_default();
+        layers.push((OVERRIDE_SOURCE.to_string(), overrides));
+        Ok(layers)
+    }
+
+    // variables added on top of the inherited environment for every command
+    // run in the project
+    pub fn project_env(&self, project: &str) -> Result<HashMap<String, String>, AppError> {
+        Ok(self.layers(project)?.into_iter().flat_map(|(_, vars)| vars).collect())
+    }
+
+    pub fn set_override(&self, project: &str, key: &str, value: Option<String>) -> Result<(), AppError> {
+        let mut overrides = self.overrides.lock().unwrap();
+        let vars = overrides.entry(project.to_string()).or_default();
+        match value {
+            Some(value) => {
+                vars.insert(key.to_string(), value);
+            }
+            None => {
+                vars.remove(key);
+            }
+        }
+        if vars.is_empty() {
+            overrides.remove(project);
+        }
+        self.save(&overrides)
+    }
+
+    pub fn rename_project(&self, old: &str, new: &str) -> Result<(), AppError> {
+        let mut overrides = self.overrides.lock().unwrap();
+        if let Some(vars) = overrides.remove(old) {
+            overrides.insert(new.to_string(), vars);
+            self.save(&overrides)?;
+        }
+        Ok(())
+    }
+}
+
+// dotenv syntax: `KEY=value`, optional `export `, # comments, single quoted
+// (literal) and double quoted (escapes, may span lines) values
+fn parse_env(content: &str) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+        if !is_valid_key(key) {
+            continue;
+        }
+
+        let value = if let Some(rest) = value.strip_prefix('"') {
+            let mut raw = rest.to_string();
+            // keep reading until the closing quote
+            while closing_quote(&raw).is_none() {
+                match lines.next() {
+                    Some(next) => {
+                        raw.push('\n');
+                        raw.push_str(next);
+                    }
+                    None => break,
+                }
+            }
+            let end = closing_quote(&raw).unwrap_or(raw.len());
+            unescape(&raw[..end])
+        } else if let Some(rest) = value.strip_prefix('\'') {
+            rest.find('\'').map(|end| &rest[..end]).unwrap_or(rest).to_string()
+        } else {
+            // unquoted values end at an inline comment
+            match value.find(" #") {
+                Some(index) => value[..index].trim_end().to_string(),
+                None => value.to_string(),
+            }
+        };
+        vars.insert(key.to_string(), value);
+    }
+    vars
+}
+
+fn closing_quote(raw: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in raw.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(index),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+fn invalid_key(key: &str) -> AppError {
+    AppError::new(ErrorCode::InvalidInput, format!("Invalid variable name: {}", key))
+        .with_hint("Use letters, digits, _, . and -, not starting with a digit")
+}
+
+fn format_value(value: &str) -> String {
+    let plain = value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:@,+".contains(c));
+    if plain && !value.is_empty() {
+        return value.to_string();
+    }
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r");
+    format!("\"{}\"", escaped)
+}
+
+// rewrites only the lines of `key` so comments and ordering survive; None
+// removes the variable
+fn update_env_file(path: &Path, key: &str, value: Option<&str>) -> Result<(), AppError> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let mut lines: Vec<String> = Vec::new();
+    let mut replaced = false;
+    let mut source = content.lines();
+    while let Some(line) = source.next() {
+        let trimmed = line.trim_start();
+        let assignment = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+        let line_key = assignment.split_once('=').map(|(line_key, _)| line_key.trim());
+        if line_key != Some(key) {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        // skip the continuation lines of a multi-line value
+        if let Some(rest) = assignment.split_once('=').map(|(_, rest)| rest.trim_start()) {
+            if let Some(rest) = rest.strip_prefix('"') {
+                let mut raw = rest.to_string();
+                while closing_quote(&raw).is_none() {
+                    match source.next() {
+                        Some(next) => raw.push_str(next),
+                        None => break,
+                    }
+                }
+            }
+        }
+        if let (Some(value), false) = (value, replaced) {
+            lines.push(format!("{}={}", key, format_value(value)));
+            replaced = true;
+        }
+    }
+    if let (Some(value), false) = (value, replaced) {
+        lines.push(format!("{}={}", key, format_value(value)));
+    }
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    fs::write(path, output).map_err(|e| AppError::io(e, "write the env file", path))
+}
+
+// deletes `key` from every env file of the project that defines it
+pub fn remove_from_env_files(project_path: &str, key: &str) -> Result<(), AppError> {
+    for name in ENV_FILES {
+        let path = Path::new(project_path).join(name);
+        let defines_key = fs::read_to_string(&path).map(|content| parse_env(&content).contains_key(key)).unwrap_or(false);
+        if defines_key {
+            update_env_file(&path, key, None)?;
+        }
+    }
+    Ok(())
+}
+
+fn env_file_path(project_path: &str, file: &str) -> Result<PathBuf, AppError> {
+    if !ENV_FILES.contains(&file) {
+        return Err(AppError::new(ErrorCode::InvalidInput, format!("Unsupported env file: {}", file))
+            .with_hint(format!("Use one of {}", ENV_FILES.join(", "))));
+    }
+    Ok(Path::new(project_path).join(file))
+}
+
+#[command]
+pub fn list_env_files(project_path: String) -> Result<Vec<EnvFile>, AppError> {
+    ENV_FILES
+        .iter()
+        .map(|name| {
+            let path = Path::new(&project_path).join(name);
+            let vars = match fs::read_to_string(&path) {
+                Ok(content) => parse_env(&content),
+                Err(_) => BTreeMap::new(),
+            };
+            Ok(EnvFile {
+                name: name.to_string(),
+                exists: path.exists(),
+                vars,
+            })
+        })
+        .collect()
+}
+
+// merged variables with the source each value comes from
+#[command]
+pub fn get_effective_env(project_path: String, state: State<'_, EnvManager>) -> Result<Vec<EnvVar>, AppError> {
+    let mut merged: BTreeMap<String, EnvVar> = BTreeMap::new();
+    for (source, vars) in state.layers(&project_path)? {
+        for (key, value) in vars {
+            match merged.get_mut(&key) {
+                Some(existing) => {
+                    let previous = std::mem::replace(&mut existing.source, source.clone());
+                    existing.shadowed.push(previous);
+                    existing.value = value;
+                }
+                None => {
+                    merged.insert(key.clone(), EnvVar {
+                        key,
+                        value,
+                        source: source.clone(),
+                        shadowed: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(merged.into_values().collect())
+}
+
+#[command]
+pub fn set_env_var(project_path: String, file: String, key: String, value: String) -> Result<(), AppError> {
+    if !is_valid_key(&key) {
+        return Err(invalid_key(&key));
+    }
+    update_env_file(&env_file_path(&project_path, &file)?, &key, Some(&value))
+}
+
+#[command]
+pub fn delete_env_var(project_path: String, file: String, key: String) -> Result<(), AppError> {
+    let path = env_file_path(&project_path, &file)?;
+    if !path.exists() {
+        return Ok(());
+    }
+    update_env_file(&path, &key, None)
+}
+
+#[command]
+pub fn set_env_override(
+    project_path: String,
+    key: String,
+    value: Option<String>,
+    state: State<'_, EnvManager>,
+) -> Result<(), AppError> {
+    if !is_valid_key(&key) {
+        return Err(invalid_key(&key));
+    }
+    state.set_override(&project_path, &key, value)
+}
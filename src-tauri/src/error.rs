@@ -0,0 +1,163 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+use serde::Serialize;
+use serde_json::Value;
+use crate::node_manager::NodeVersionMismatch;
+
+// stable identifiers the frontend can switch on, serialized in snake_case
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    // the folder has no package.json
+    NotANodeProject,
+    InvalidPackageJson,
+    NotFound,
+    PermissionDenied,
+    // npm / pnpm / yarn or another executable isn't installed
+    PackageManagerMissing,
+    UnsupportedRuntime,
+    UnsupportedFramework,
+    NodeVersionMismatch,
+    // the project has secrets and the store needs the master passphrase
+    SecretsLocked,
+    WrongPassphrase,
+    // node_modules doesn't have a package the command needs
+    DependenciesMissing,
+    AlreadyRunning,
+    // a node version, port reservation or registered project that exists already
+    AlreadyExists,
+    // reserved for another project, or nothing free in the range
+    PortUnavailable,
+    ShuttingDown,
+    // the operating system has no way to do it, e.g. opening an IDE
+    UnsupportedPlatform,
+    // key derivation, encryption or certificate generation failed
+    Crypto,
+    // a process couldn't be signalled or wouldn't exit
+    Process,
+    InvalidInput,
+    Io,
+    // anything not classified yet
+    Failed,
+}
+
+// what every command returns on failure: `{ code, message, hint, context, details }`
+#[derive(Serialize, Clone, Debug)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    // what the user can do about it
+    pub hint: Option<String>,
+    // the path or operation the error came from
+    pub context: Option<String>,
+    // structured payload for codes that have one, e.g. the node mismatch
+    pub details: Option<Value>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> AppError {
+        AppError {
+            code,
+            message: message.into(),
+            hint: None,
+            context: None,
+            details: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> AppError {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    pub fn with_context(mut self, context: impl Into<String>) -> AppError {
+        self.context = Some(context.into());
+        self
+    }
+
+    // classifies by io::ErrorKind, `action` reads like "Failed to <action>"
+    pub fn io(error: io::Error, action: &str, path: &Path) -> AppError {
+        let code = match error.kind() {
+            io::ErrorKind::NotFound => ErrorCode::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            _ => ErrorCode::Io,
+        };
+        let error = AppError::new(code, format!("Failed to {}: {}", action, error)).with_context(path.display().to_string());
+        match code {
+            ErrorCode::PermissionDenied => error.with_hint("Check that dev-thing can read and write this folder"),
+            _ => error,
+        }
+    }
+
+    pub fn unsupported_platform() -> AppError {
+        AppError::new(ErrorCode::UnsupportedPlatform, format!("Unsupported operating system: {}", std::env::consts::OS))
+    }
+
+    pub fn unsupported_runtime(runtime: &str) -> AppError {
+        AppError::new(ErrorCode::UnsupportedRuntime, format!("Unsupported runtime: {}", runtime))
+            .with_hint("Use npm, pnpm, yarn, bun or deno")
+    }
+
+    pub fn package_manager_missing(program: &str) -> AppError {
+        AppError::new(ErrorCode::PackageManagerMissing, format!("{} is not installed or not on PATH", program))
+            .with_hint(format!("Install {} or enable it with `corepack enable`", program))
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+// failures there's nothing to classify about, e.g. serialization or
+// signalling a process; anything the user can act on gets its own code
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::new(ErrorCode::Failed, message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::new(ErrorCode::Failed, message)
+    }
+}
+
+impl From<NodeVersionMismatch> for AppError {
+    fn from(mismatch: NodeVersionMismatch) -> Self {
+        let hint = if mismatch.required.is_empty() {
+            None
+        } else {
+            Some(format!("Install Node {} or pin a compatible version for the project", mismatch.required))
+        };
+        AppError {
+            code: ErrorCode::NodeVersionMismatch,
+            message: mismatch.message.clone(),
+            hint,
+            context: (!mismatch.source.is_empty()).then(|| mismatch.source.clone()),
+            details: serde_json::to_value(&mismatch).ok(),
+        }
+    }
+}
+
+// package.json of the project, telling a missing file (not a node project)
+// apart from one that can't be read or parsed
+pub fn read_package_json(project_path: &str) -> Result<Value, AppError> {
+    let path = Path::new(project_path).join("package.json");
+    let content = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => AppError::new(ErrorCode::NotANodeProject, format!("{} has no package.json", project_path))
+            .with_hint("Choose the folder that contains the project's package.json")
+            .with_context(path.display().to_string()),
+        _ => AppError::io(e, "read package.json", &path),
+    })?;
+
+    serde_json::from_str(&content).map_err(|e| {
+        AppError::new(ErrorCode::InvalidPackageJson, format!("Failed to parse package.json: {}", e))
+            .with_hint("Fix the syntax error in package.json")
+            .with_context(format!("{}:{}:{}", path.display(), e.line(), e.column()))
+    })
+}
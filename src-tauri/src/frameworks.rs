@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+use serde::Serialize;
+use serde_json::Value;
+
+// how a framework's dev / build / start command is run
+#[derive(Clone, Copy, Debug)]
+pub enum LaunchCommand {
+    // a binary of one of the project's packages, run with the project's node:
+    // `next dev` is Bin { package: "next", bin: "next", args: &["dev"] }
+    Bin {
+        package: &'static str,
+        bin: &'static str,
+        args: &'static [&'static str],
+    },
+    // the first of these package.json scripts that exists, run through the
+    // package manager (servers like express have no cli of their own)
+    Script(&'static [&'static str]),
+}
+
+impl fmt::Display for LaunchCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaunchCommand::Bin { bin, args, .. } => {
+                write!(f, "{}", bin)?;
+                for arg in args.iter() {
+                    write!(f, " {}", arg)?;
+                }
+                Ok(())
+            }
+            LaunchCommand::Script(scripts) => write!(f, "run {}", scripts.join(" | ")),
+        }
+    }
+}
+
+// how the dev server is told which port to listen on, on top of PORT which
+// is always set
+#[derive(Clone, Copy, Debug)]
+pub enum PortFlag {
+    // only the PORT variable
+    Env,
+    // `--port <port>`
+    Flag,
+    // vite: `--port <port> --strictPort` so it fails instead of silently
+    // moving to the next port
+    Strict,
+}
+
+pub trait FrameworkDetector: Sync + Send {
+    fn name(&self) -> &'static str;
+    // 0 when the project doesn't use the framework, up to 100
+    fn confidence(&self, package_json: &Value, project_path: &Path) -> u8;
+    fn dev_command(&self) -> Option<LaunchCommand>;
+    fn build_command(&self) -> Option<LaunchCommand>;
+    fn start_command(&self) -> Option<LaunchCommand>;
+    fn default_port(&self) -> Option<u16>;
+    // extra cli arguments and environment to make the dev server listen on `port`
+    fn port_overrides(&self, port: u16) -> (Vec<String>, HashMap<String, String>);
+}
+
+// a framework recognized by one of its packages in dependencies or
+// devDependencies, and optionally its config file
+pub struct PackageFramework {
+    name: &'static str,
+    packages: &'static [&'static str],
+    config_files: &'static [&'static str],
+    // confidence when the package is a dependency, meta-frameworks score
+    // higher than the libraries they are built on (Next.js over React, SvelteKit over Vite)
+    weight: u8,
+    dev: Option<LaunchCommand>,
+    build: Option<LaunchCommand>,
+    start: Option<LaunchCommand>,
+    port: Option<u16>,
+    port_flag: PortFlag,
+    // framework specific port variable, e.g. NUXT_PORT
+    port_env: Option<&'static str>,
+}
+
+impl FrameworkDetector for PackageFramework {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn confidence(&self, package_json: &Value, project_path: &Path) -> u8 {
+        let declared_in = |field: &str| self.packages.iter().any(|package| package_json[field].get(package).is_some());
+        let mut confidence = if declared_in("dependencies") {
+            self.weight
+        } else if declared_in("devDependencies") {
+            // tooling-only frameworks (vite, astro) usually live here
+            self.weight.saturating_sub(5)
+        } else {
+            return 0;
+        };
+        if self.config_files.iter().any(|file| project_path.join(file).exists()) {
+            confidence = confidence.saturating_add(10);
+        }
+        confidence.min(100)
+    }
+
+    fn dev_command(&self) -> Option<LaunchCommand> {
+        self.dev
+    }
+
+    fn build_command(&self) -> Option<LaunchCommand> {
+        self.build
+    }
+
+    fn start_command(&self) -> Option<LaunchCommand> {
+        self.start
+    }
+
+    fn default_port(&self) -> Option<u16> {
+        self.port
+    }
+
+    fn port_overrides(&self, port: u16) -> (Vec<String>, HashMap<String, String>) {
+        let port_string = port.to_string();
+        let mut envs = HashMap::from([("PORT".to_string(), port_string.clone())]);
+        if let Some(name) = self.port_env {
+            envs.insert(name.to_string(), port_string.clone());
+        }
+        let args = match self.port_flag {
+            PortFlag::Env => Vec::new(),
+            PortFlag::Flag => vec!["--port".to_string(), port_string],
+            PortFlag::Strict => vec!["--port".to_string(), port_string, "--strictPort".to_string()],
+        };
+        (args, envs)
+    }
+}
+
+const fn bin(package: &'static str, bin: &'static str, args: &'static [&'static str]) -> Option<LaunchCommand> {
+    Some(LaunchCommand::Bin { package, bin, args })
+}
+
+const fn script(scripts: &'static [&'static str]) -> Option<LaunchCommand> {
+    Some(LaunchCommand::Script(scripts))
+}
+
+const VITE_CONFIGS: &[&str] = &["vite.config.ts", "vite.config.js", "vite.config.mjs", "vite.config.mts"];
+
+static BUILT_IN: [PackageFramework; 16] = [
+    PackageFramework {
+        name: "Next.js",
+        packages: &["next"],
+        config_files: &["next.config.js", "next.config.mjs", "next.config.ts"],
+        weight: 90,
+        dev: bin("next", "next", &["dev"]),
+        build: bin("next", "next", &["build"]),
+        start: bin("next", "next", &["start"]),
+        port: Some(3000),
+        port_flag: PortFlag::Flag,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Nuxt.js",
+        packages: &["nuxt"],
+        config_files: &["nuxt.config.ts", "nuxt.config.js"],
+        weight: 90,
+        dev: bin("nuxt", "nuxt", &["dev"]),
+        build: bin("nuxt", "nuxt", &["build"]),
+        start: bin("nuxt", "nuxt", &["preview"]),
+        port: Some(3000),
+        port_flag: PortFlag::Flag,
+        port_env: Some("NUXT_PORT"),
+    },
+    PackageFramework {
+        name: "SvelteKit",
+        packages: &["@sveltejs/kit"],
+        config_files: &["svelte.config.js"],
+        weight: 90,
+        dev: bin("vite", "vite", &["dev"]),
+        build: bin("vite", "vite", &["build"]),
+        start: bin("vite", "vite", &["preview"]),
+        port: Some(5173),
+        port_flag: PortFlag::Strict,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Astro",
+        packages: &["astro"],
+        config_files: &["astro.config.mjs", "astro.config.ts"],
+        weight: 90,
+        dev: bin("astro", "astro", &["dev"]),
+        build: bin("astro", "astro", &["build"]),
+        start: bin("astro", "astro", &["preview"]),
+        port: Some(4321),
+        port_flag: PortFlag::Flag,
+        port_env: None,
+    },
+    // classic and vite based remix use different dev commands, the
+    // project's own script knows which one it is
+    PackageFramework {
+        name: "Remix",
+        packages: &["@remix-run/dev"],
+        config_files: &["remix.config.js"],
+        weight: 90,
+        dev: script(&["dev"]),
+        build: script(&["build"]),
+        start: script(&["start"]),
+        port: Some(5173),
+        port_flag: PortFlag::Flag,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Angular",
+        packages: &["@angular/core"],
+        config_files: &["angular.json"],
+        weight: 90,
+        dev: bin("@angular/cli", "ng", &["serve"]),
+        build: bin("@angular/cli", "ng", &["build"]),
+        start: None,
+        port: Some(4200),
+        port_flag: PortFlag::Flag,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "SolidStart",
+        packages: &["@solidjs/start"],
+        config_files: &["app.config.ts", "app.config.js"],
+        weight: 90,
+        dev: bin("vinxi", "vinxi", &["dev"]),
+        build: bin("vinxi", "vinxi", &["build"]),
+        start: bin("vinxi", "vinxi", &["start"]),
+        port: Some(3000),
+        port_flag: PortFlag::Flag,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Gatsby",
+        packages: &["gatsby"],
+        config_files: &["gatsby-config.js", "gatsby-config.ts"],
+        weight: 90,
+        dev: bin("gatsby", "gatsby", &["develop"]),
+        build: bin("gatsby", "gatsby", &["build"]),
+        start: bin("gatsby", "gatsby", &["serve"]),
+        port: Some(8000),
+        port_flag: PortFlag::Flag,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "NestJS",
+        packages: &["@nestjs/core"],
+        config_files: &["nest-cli.json"],
+        weight: 85,
+        dev: bin("@nestjs/cli", "nest", &["start", "--watch"]),
+        build: bin("@nestjs/cli", "nest", &["build"]),
+        start: script(&["start:prod", "start"]),
+        port: Some(3000),
+        port_flag: PortFlag::Env,
+        port_env: None,
+    },
+    // create-react-app
+    PackageFramework {
+        name: "React",
+        packages: &["react-scripts"],
+        config_files: &[],
+        weight: 80,
+        dev: bin("react-scripts", "react-scripts", &["start"]),
+        build: bin("react-scripts", "react-scripts", &["build"]),
+        start: None,
+        port: Some(3000),
+        port_flag: PortFlag::Env,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Electron",
+        packages: &["electron"],
+        config_files: &["forge.config.js", "electron.vite.config.ts"],
+        weight: 70,
+        dev: script(&["dev", "start"]),
+        build: script(&["build", "make", "package"]),
+        start: bin("electron", "electron", &["."]),
+        port: None,
+        port_flag: PortFlag::Env,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Vue.js",
+        packages: &["vue"],
+        config_files: VITE_CONFIGS,
+        weight: 65,
+        dev: bin("vite", "vite", &[]),
+        build: bin("vite", "vite", &["build"]),
+        start: bin("vite", "vite", &["preview"]),
+        port: Some(5173),
+        port_flag: PortFlag::Strict,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Vite",
+        packages: &["vite"],
+        config_files: VITE_CONFIGS,
+        weight: 60,
+        dev: bin("vite", "vite", &[]),
+        build: bin("vite", "vite", &["build"]),
+        start: bin("vite", "vite", &["preview"]),
+        port: Some(5173),
+        port_flag: PortFlag::Strict,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Fastify",
+        packages: &["fastify"],
+        config_files: &[],
+        weight: 45,
+        dev: script(&["dev", "start"]),
+        build: script(&["build"]),
+        start: script(&["start"]),
+        port: Some(3000),
+        port_flag: PortFlag::Env,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Hono",
+        packages: &["hono"],
+        config_files: &[],
+        weight: 45,
+        dev: script(&["dev", "start"]),
+        build: script(&["build"]),
+        start: script(&["start"]),
+        port: Some(3000),
+        port_flag: PortFlag::Env,
+        port_env: None,
+    },
+    PackageFramework {
+        name: "Express",
+        packages: &["express"],
+        config_files: &[],
+        weight: 40,
+        dev: script(&["dev", "start"]),
+        build: script(&["build"]),
+        start: script(&["start"]),
+        port: Some(3000),
+        port_flag: PortFlag::Env,
+        port_env: None,
+    },
+];
+
+// every detector dev-thing knows, in priority order
+pub struct FrameworkRegistry {
+    detectors: Vec<&'static dyn FrameworkDetector>,
+}
+
+impl FrameworkRegistry {
+    fn built_in() -> FrameworkRegistry {
+        FrameworkRegistry {
+            detectors: BUILT_IN.iter().map(|framework| framework as &dyn FrameworkDetector).collect(),
+        }
+    }
+
+    // the most confident match; on a tie the detector registered first wins
+    pub fn detect(&self, package_json: &Value, project_path: &Path) -> Option<DetectedFramework> {
+        let mut best: Option<DetectedFramework> = None;
+        for detector in &self.detectors {
+            let confidence = detector.confidence(package_json, project_path);
+            if confidence > best.as_ref().map(|best| best.confidence).unwrap_or(0) {
+                best = Some(DetectedFramework { detector: *detector, confidence });
+            }
+        }
+        best
+    }
+}
+
+pub fn registry() -> &'static FrameworkRegistry {
+    static REGISTRY: OnceLock<FrameworkRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(FrameworkRegistry::built_in)
+}
+
+pub fn detect(package_json: &Value, project_path: &str) -> Option<DetectedFramework> {
+    registry().detect(package_json, Path::new(project_path))
+}
+
+pub struct DetectedFramework {
+    pub detector: &'static dyn FrameworkDetector,
+    pub confidence: u8,
+}
+
+// what the frontend gets to show about the detected framework
+#[derive(Serialize)]
+pub struct FrameworkSummary {
+    name: String,
+    confidence: u8,
+    dev_command: Option<String>,
+    build_command: Option<String>,
+    start_command: Option<String>,
+    default_port: Option<u16>,
+}
+
+impl From<&DetectedFramework> for FrameworkSummary {
+    fn from(detected: &DetectedFramework) -> Self {
+        let detector = detected.detector;
+        FrameworkSummary {
+            name: detector.name().to_string(),
+            confidence: detected.confidence,
+            dev_command: detector.dev_command().map(|command| command.to_string()),
+            build_command: detector.build_command().map(|command| command.to_string()),
+            start_command: detector.start_command().map(|command| command.to_string()),
+            default_port: detector.default_port(),
+        }
+    }
+}
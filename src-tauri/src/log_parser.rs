@@ -0,0 +1,154 @@
+use chrono::Local;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+// payload of the `project-output` / `project-error` events
+#[derive(Serialize, Clone, Debug)]
+pub struct LogRecord {
+    pub pid: u32,
+    pub project: String,
+    pub process: String,
+    pub timestamp: String,
+    // stdout or stderr
+    pub stream: String,
+    pub level: LogLevel,
+    // the line without ANSI escape codes
+    pub text: String,
+    pub urls: Vec<String>,
+    // port of the first local server url (or "listening on port ...") in the line
+    pub port: Option<u16>,
+}
+
+struct Patterns {
+    ansi: Regex,
+    url: Regex,
+    local_url: Regex,
+    listening_port: Regex,
+    prefixed_level: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        // CSI sequences (colors, cursor movement) and OSC sequences (hyperlinks, titles)
+        ansi: Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-Z\\-_]").unwrap(),
+        url: Regex::new(r#"https?://[^\s'"<>`]+"#).unwrap(),
+        local_url: Regex::new(r"^https?://(?:localhost|127\.0\.0\.1|0\.0\.0\.0|\[::1?\]|[\w-]+\.localhost):(\d{2,5})").unwrap(),
+        listening_port: Regex::new(r"(?i)\b(?:listening|running|started|ready|serving|available)\b.*?\bport\b\s*:?\s*(\d{2,5})").unwrap(),
+        // winston's simple format ("warn: ..."), next 12/13 ("warn  - ...") and
+        // bracketed or upper case levels used by consola, esbuild and most loggers
+        prefixed_level: Regex::new(r"(?i)^\s*(?:\[\w+\]\s*)?[\[(]?(trace|debug|verbose|silly|info|http|event|wait|ready|warn|warning|error|err|fatal|critical)[\])]?\s*(?:-|:|\s)").unwrap(),
+    })
+}
+
+pub fn strip_ansi(line: &str) -> String {
+    patterns().ansi.replace_all(line, "").to_string()
+}
+
+pub fn parse_line(pid: u32, project: &str, process: &str, stream: &str, line: &str) -> LogRecord {
+    let text = strip_ansi(line);
+    let urls: Vec<String> = patterns()
+        .url
+        .find_iter(&text)
+        .map(|url| url.as_str().trim_end_matches(['.', ',', ';', ')', ']', '\'']).to_string())
+        .collect();
+    let port = urls
+        .iter()
+        .find_map(|url| patterns().local_url.captures(url))
+        .or_else(|| patterns().listening_port.captures(&text))
+        .and_then(|captures| captures[1].parse().ok());
+
+    LogRecord {
+        pid,
+        project: project.to_string(),
+        process: process.to_string(),
+        timestamp: Local::now().to_rfc3339(),
+        stream: stream.to_string(),
+        level: detect_level(&text, stream),
+        text,
+        urls,
+        port,
+    }
+}
+
+fn detect_level(text: &str, stream: &str) -> LogLevel {
+    let trimmed = text.trim_start();
+
+    // pino / bunyan (numeric) and winston json (named) levels
+    if trimmed.starts_with('{') {
+        if let Ok(json) = serde_json::from_str::<Value>(trimmed) {
+            match &json["level"] {
+                Value::Number(level) => return pino_level(level.as_u64().unwrap_or(30)),
+                Value::String(level) => {
+                    if let Some(level) = named_level(level) {
+                        return level;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // next 13+ / nuxt / vite status symbols
+    match trimmed.chars().next() {
+        Some('⨯') | Some('✖') | Some('✘') | Some('×') => return LogLevel::Error,
+        Some('⚠') => return LogLevel::Warn,
+        Some('✓') | Some('✔') | Some('ℹ') | Some('➜') | Some('○') | Some('◐') => return LogLevel::Info,
+        _ => {}
+    }
+
+    if let Some(captures) = patterns().prefixed_level.captures(trimmed) {
+        if let Some(level) = named_level(&captures[1]) {
+            return level;
+        }
+    }
+
+    if trimmed.contains("[vite] Internal server error") || trimmed.starts_with("Error:") || trimmed.contains("[ERROR]") {
+        return LogLevel::Error;
+    }
+    if trimmed.contains("[WARNING]") {
+        return LogLevel::Warn;
+    }
+    if stream == "stderr" {
+        LogLevel::Warn
+    } else {
+        LogLevel::Info
+    }
+}
+
+fn pino_level(level: u64) -> LogLevel {
+    match level {
+        0..=10 => LogLevel::Trace,
+        11..=20 => LogLevel::Debug,
+        21..=30 => LogLevel::Info,
+        31..=40 => LogLevel::Warn,
+        41..=50 => LogLevel::Error,
+        _ => LogLevel::Fatal,
+    }
+}
+
+fn named_level(level: &str) -> Option<LogLevel> {
+    let level = match level.to_lowercase().as_str() {
+        "trace" | "silly" => LogLevel::Trace,
+        "debug" | "verbose" => LogLevel::Debug,
+        "info" | "http" | "event" | "wait" | "ready" | "notice" => LogLevel::Info,
+        "warn" | "warning" => LogLevel::Warn,
+        "error" | "err" => LogLevel::Error,
+        "fatal" | "critical" | "crit" | "emerg" | "alert" => LogLevel::Fatal,
+        _ => return None,
+    };
+    Some(level)
+}
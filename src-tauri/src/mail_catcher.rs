@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::TcpListener as StdTcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use chrono::Local;
+use mail_parser::{Address, Message, MessageParser, MimeHeaders};
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use crate::utils::project_slug;
+use crate::error::{AppError, ErrorCode};
+
+// same limit most providers use, advertised in EHLO
+const MAX_MESSAGE_BYTES: usize = 25 * 1024 * 1024;
+// longest line read at once; commands are far shorter but AUTH responses may
+// be up to 12288 bytes, longer DATA lines are read in pieces
+const MAX_LINE_BYTES: usize = 16 * 1024;
+
+// one SMTP listener per project on a local port, so captured mail can be
+// attributed without any configuration in the app itself; messages are
+// stored as `dir/<project slug>/<id>.eml` with the envelope in `<id>.json`
+pub struct MailCatcher {
+    dir: PathBuf,
+    servers: Mutex<HashMap<String, u16>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Envelope {
+    from: String,
+    to: Vec<String>,
+    received_at: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct MailSummary {
+    id: String,
+    project: String,
+    from: Vec<String>,
+    to: Vec<String>,
+    subject: Option<String>,
+    date: Option<String>,
+    received_at: String,
+    envelope_from: String,
+    envelope_to: Vec<String>,
+    size: usize,
+    has_html: bool,
+    attachments: usize,
+}
+
+#[derive(Serialize)]
+pub struct MailMessage {
+    #[serde(flatten)]
+    summary: MailSummary,
+    cc: Vec<String>,
+    headers: Vec<(String, String)>,
+    text: Option<String>,
+    html: Option<String>,
+    attachments: Vec<MailAttachment>,
+}
+
+#[derive(Serialize)]
+pub struct MailAttachment {
+    index: usize,
+    name: Option<String>,
+    content_type: Option<String>,
+    size: usize,
+}
+
+impl MailCatcher {
+    pub fn new(dir: PathBuf) -> MailCatcher {
+        MailCatcher {
+            dir,
+            servers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn project_dir(&self, project: &str) -> PathBuf {
+        self.dir.join(project_slug(project))
+    }
+
+    pub fn server_port(&self, project: &str) -> Option<u16> {
+        self.servers.lock().unwrap().get(project).copied()
+    }
+
+    // port of the project's SMTP server, started on first use and kept for
+    // the rest of the session so a restarted dev server gets the same port
+    pub fn ensure_server(&self, project: &str, window: tauri::Window) -> Result<u16, AppError> {
+        let mut servers = self.servers.lock().unwrap();
+        if let Some(&port) = servers.get(project) {
+            return Ok(port);
+        }
+
+        let listener = StdTcpListener::bind(("127.0.0.1", 0))
+            .map_err(|e| AppError::new(ErrorCode::PortUnavailable, format!("Failed to start mail catcher: {}", e)))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| AppError::new(ErrorCode::PortUnavailable, format!("Failed to start mail catcher: {}", e)))?
+            .port();
+        let dir = self.project_dir(project);
+        let project_name = project.to_string();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = serve(listener, project_name, dir, window).await {
+                println!("Mail catcher stopped: {}", e);
+            }
+        });
+
+        println!("Mail catcher for {} listening on port {}", project, port);
+        servers.insert(project.to_string(), port);
+        Ok(port)
+    }
+
+    fn message_path(&self, project: &str, id: &str) -> Result<PathBuf, AppError> {
+        // ids are generated by us, anything else could escape the directory
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(AppError::new(ErrorCode::InvalidInput, format!("Invalid message id: {}", id)));
+        }
+        let path = self.project_dir(project).join(format!("{}.eml", id));
+        if !path.exists() {
+            return Err(AppError::new(ErrorCode::NotFound, format!("Message {} not found", id)));
+        }
+        Ok(path)
+    }
+
+    fn message_ids(&self, project: &str) -> Vec<String> {
+        let mut ids: Vec<String> = fs::read_dir(self.project_dir(project))
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| {
+                        let file_name = entry.file_name().to_string_lossy().to_string();
+                        file_name.strip_suffix(".eml").map(|id| id.to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        // ids start with the receive time, newest first
+        ids.sort_by(|a, b| b.cmp(a));
+        ids
+    }
+}
+
+async fn serve(listener: StdTcpListener, project: String, dir: PathBuf, window: tauri::Window) -> std::io::Result<()> {
+    listener.set_nonblocking(true)?;
+    let listener = TcpListener::from_std(listener)?;
+    loop {
+        let (client, _) = listener.accept().await?;
+        let (project, dir, window) = (project.clone(), dir.clone(), window.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = handle_session(client, &project, &dir, &window).await {
+                println!("Mail catcher session failed: {}", e);
+            }
+        });
+    }
+}
+
+// a permissive SMTP server: any sender, any recipient, any credentials
+async fn handle_session(stream: TcpStream, project: &str, dir: &Path, window: &tauri::Window) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut from = String::new();
+    let mut to: Vec<String> = Vec::new();
+
+    writer.write_all(b"220 dev-thing mail catcher ESMTP\r\n").await?;
+    loop {
+        let line = match read_line(&mut reader).await {
+            Ok(Some(line)) => line,
+            Ok(None) => return Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                writer.write_all(b"500 Line too long\r\n").await?;
+                return Err(e);
+            }
+            Err(e) => return Err(e),
+        };
+        let upper = line.to_ascii_uppercase();
+        let verb = upper.split_whitespace().next().unwrap_or("");
+
+        let reply = match verb {
+            "EHLO" => format!(
+                "250-dev-thing\r\n250-PIPELINING\r\n250-8BITMIME\r\n250-SMTPUTF8\r\n250-SIZE {}\r\n250 AUTH PLAIN LOGIN\r\n",
+                MAX_MESSAGE_BYTES
+            ),
+            "HELO" => "250 dev-thing\r\n".to_string(),
+            "AUTH" => {
+                // mailers configured with credentials insist on authenticating
+                let mut arguments = upper.split_whitespace().skip(1);
+                match (arguments.next(), arguments.next()) {
+                    (Some("PLAIN"), None) => {
+                        writer.write_all(b"334 \r\n").await?;
+                        read_line(&mut reader).await?;
+                    }
+                    (Some("LOGIN"), initial) => {
+                        if initial.is_none() {
+                            writer.write_all(b"334 VXNlcm5hbWU6\r\n").await?;
+                            read_line(&mut reader).await?;
+                        }
+                        writer.write_all(b"334 UGFzc3dvcmQ6\r\n").await?;
+                        read_line(&mut reader).await?;
+                    }
+                    _ => {}
+                }
+                "235 Authentication successful\r\n".to_string()
+            }
+            "MAIL" => {
+                from = angle_address(&line);
+                to.clear();
+                "250 OK\r\n".to_string()
+            }
+            "RCPT" => {
+                to.push(angle_address(&line));
+                "250 OK\r\n".to_string()
+            }
+            "DATA" if to.is_empty() => "503 Need RCPT command\r\n".to_string(),
+            "DATA" => {
+                writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await?;
+                let reply = match read_data(&mut reader).await? {
+                    Some(data) => {
+                        let envelope = Envelope {
+                            from: std::mem::take(&mut from),
+                            to: std::mem::take(&mut to),
+                            received_at: Local::now().to_rfc3339(),
+                        };
+                        match store_message(project, dir, &envelope, &data) {
+                            Ok(summary) => {
+                                let reply = format!("250 OK queued as {}\r\n", summary.id);
+                                window.emit("mail-received", summary).unwrap();
+                                reply
+                            }
+                            Err(e) => {
+                                println!("Failed to store mail: {}", e);
+                                "451 Failed to store message\r\n".to_string()
+                            }
+                        }
+                    }
+                    None => "552 Message exceeds fixed maximum message size\r\n".to_string(),
+                };
+                reply
+            }
+            "RSET" => {
+                from.clear();
+                to.clear();
+                "250 OK\r\n".to_string()
+            }
+            "NOOP" => "250 OK\r\n".to_string(),
+            "VRFY" => "252 Cannot verify user\r\n".to_string(),
+            "QUIT" => {
+                writer.write_all(b"221 Bye\r\n").await?;
+                return Ok(());
+            }
+            _ => "502 Command not implemented\r\n".to_string(),
+        };
+        writer.write_all(reply.as_bytes()).await?;
+    }
+}
+
+// up to MAX_LINE_BYTES, a longer line fails with InvalidData
+async fn read_line<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> std::io::Result<Option<String>> {
+    let mut line = Vec::new();
+    if reader.take(MAX_LINE_BYTES as u64).read_until(b'\n', &mut line).await? == 0 {
+        return Ok(None);
+    }
+    if line.len() == MAX_LINE_BYTES && !line.ends_with(b"\n") {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "line too long"));
+    }
+    Ok(Some(String::from_utf8_lossy(&line).trim_end().to_string()))
+}
+
+// message body up to the lone "." line with dot stuffing removed, None if it
+// is too large (the rest is still consumed to keep the session in sync)
+async fn read_data<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> std::io::Result<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    let mut too_large = false;
+    // false while reading the rest of a line longer than MAX_LINE_BYTES
+    let mut line_start = true;
+    loop {
+        let mut line = Vec::new();
+        if reader.take(MAX_LINE_BYTES as u64).read_until(b'\n', &mut line).await? == 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "connection closed during DATA"));
+        }
+        if line_start && (line == b".\r\n" || line == b".\n") {
+            break;
+        }
+        if !too_large {
+            let part = if line_start { line.strip_prefix(b".").unwrap_or(&line) } else { &line };
+            data.extend_from_slice(part);
+            too_large = data.len() > MAX_MESSAGE_BYTES;
+        }
+        line_start = line.ends_with(b"\n");
+    }
+    Ok(if too_large { None } else { Some(data) })
+}
+
+// "MAIL FROM:<a@b.c> SIZE=123" -> "a@b.c"
+fn angle_address(line: &str) -> String {
+    let value = line.split_once(':').map(|(_, value)| value.trim()).unwrap_or("");
+    match (value.find('<'), value.find('>')) {
+        (Some(start), Some(end)) if start < end => value[start + 1..end].to_string(),
+        _ => value.split_whitespace().next().unwrap_or("").to_string(),
+    }
+}
+
+fn store_message(project: &str, dir: &Path, envelope: &Envelope, data: &[u8]) -> Result<MailSummary, AppError> {
+    fs::create_dir_all(dir).map_err(|e| AppError::io(e, "create mail directory", dir))?;
+    let mut id = Local::now().format("%Y%m%d%H%M%S%f").to_string();
+    // two messages in the same microsecond
+    while dir.join(format!("{}.eml", id)).exists() {
+        id.push('0');
+    }
+    let envelope_path = dir.join(format!("{}.json", id));
+    let content = serde_json::to_string_pretty(envelope).map_err(|e| e.to_string())?;
+    fs::write(&envelope_path, content).map_err(|e| AppError::io(e, "save message", &envelope_path))?;
+    let message_path = dir.join(format!("{}.eml", id));
+    fs::write(&message_path, data).map_err(|e| AppError::io(e, "save message", &message_path))?;
+
+    let message = parse_message(data)?;
+    Ok(summarize(project, &id, envelope, &message, data.len()))
+}
+
+fn read_envelope(path: &Path) -> Envelope {
+    fs::read_to_string(path.with_extension("json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| Envelope {
+            from: String::new(),
+            to: Vec::new(),
+            received_at: String::new(),
+        })
+}
+
+fn summarize(project: &str, id: &str, envelope: &Envelope, message: &Message, size: usize) -> MailSummary {
+    MailSummary {
+        id: id.to_string(),
+        project: project.to_string(),
+        from: addresses(message.from()),
+        to: addresses(message.to()),
+        subject: message.subject().map(|subject| subject.to_string()),
+        date: message.date().map(|date| date.to_rfc3339()),
+        received_at: envelope.received_at.clone(),
+        envelope_from: envelope.from.clone(),
+        envelope_to: envelope.to.clone(),
+        size,
+        has_html: html_body(message).is_some(),
+        attachments: message.attachment_count(),
+    }
+}
+
+// "Jane Doe <jane@example.com>"
+fn addresses(address: Option<&Address>) -> Vec<String> {
+    address
+        .map(|address| {
+            address
+                .iter()
+                .map(|addr| match (&addr.name, &addr.address) {
+                    (Some(name), Some(email)) => format!("{} <{}>", name, email),
+                    (None, Some(email)) => email.to_string(),
+                    (Some(name), None) => name.to_string(),
+                    (None, None) => String::new(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// mail_parser lists text-only parts among the html bodies too, only real
+// html counts so the viewer shows the text body for plain messages
+fn html_body(message: &Message) -> Option<String> {
+    message
+        .html_bodies()
+        .find(|part| part.is_text_html())
+        .and_then(|part| part.text_contents())
+        .map(|html| html.to_string())
+}
+
+fn read_message(path: &Path) -> Result<Vec<u8>, AppError> {
+    fs::read(path).map_err(|e| AppError::io(e, "read message", path))
+}
+
+fn parse_message(data: &[u8]) -> Result<Message<'_>, AppError> {
+    MessageParser::default().parse(data).ok_or_else(|| AppError::new(ErrorCode::InvalidInput, "Could not parse message"))
+}
+
+#[command]
+pub fn get_mail_server(project_path: String, state: State<'_, MailCatcher>) -> Result<Option<u16>, AppError> {
+    Ok(state.server_port(&project_path))
+}
+
+#[command]
+pub fn list_mail(project_path: String, state: State<'_, MailCatcher>) -> Result<Vec<MailSummary>, AppError> {
+    let dir = state.project_dir(&project_path);
+    let mut messages = Vec::new();
+    for id in state.message_ids(&project_path) {
+        let path = dir.join(format!("{}.eml", id));
+        let data = match read_message(&path) {
+            Ok(data) => data,
+            Err(_) => continue,
+        };
+        if let Some(message) = MessageParser::default().parse(&data) {
+            messages.push(summarize(&project_path, &id, &read_envelope(&path), &message, data.len()));
+        }
+    }
+    Ok(messages)
+}
+
+#[command]
+pub fn get_mail(project_path: String, id: String, state: State<'_, MailCatcher>) -> Result<MailMessage, AppError> {
+    let path = state.message_path(&project_path, &id)?;
+    let data = read_message(&path)?;
+    let message = parse_message(&data)?;
+
+    let attachments = message
+        .attachments()
+        .enumerate()
+        .map(|(index, part)| MailAttachment {
+            index,
+            name: part.attachment_name().map(|name| name.to_string()),
+            content_type: part.content_type().map(|content_type| match content_type.subtype() {
+                Some(subtype) => format!("{}/{}", content_type.ctype(), subtype),
+                None => content_type.ctype().to_string(),
+            }),
+            size: part.len(),
+        })
+        .collect();
+
+    Ok(MailMessage {
+        summary: summarize(&project_path, &id, &read_envelope(&path), &message, data.len()),
+        cc: addresses(message.cc()),
+        headers: message
+            .headers_raw()
+            .map(|(name, value)| (name.to_string(), value.trim().to_string()))
+            .collect(),
+        text: message.body_text(0).map(|text| text.to_string()),
+        html: html_body(&message),
+        attachments,
+    })
+}
+
+// writes attachment `index` of the message to `destination` (a file, or a
+// directory to keep the attachment's own name) and returns the written path
+#[command]
+pub fn save_mail_attachment(
+    project_path: String,
+    id: String,
+    index: usize,
+    destination: String,
+    state: State<'_, MailCatcher>,
+) -> Result<String, AppError> {
+    let data = read_message(&state.message_path(&project_path, &id)?)?;
+    let message = parse_message(&data)?;
+    let attachment = message
+        .attachment(index)
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, format!("Attachment {} not found", index)))?;
+
+    let mut path = PathBuf::from(&destination);
+    if path.is_dir() {
+        let name = attachment
+            .attachment_name()
+            .and_then(|name| Path::new(name).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(format!("attachment-{}", index));
+        path = path.join(name);
+    }
+    fs::write(&path, attachment.contents()).map_err(|e| AppError::io(e, "save attachment", &path))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[command]
+pub fn delete_mail(project_path: String, id: String, state: State<'_, MailCatcher>) -> Result<(), AppError> {
+    let path = state.message_path(&project_path, &id)?;
+    fs::remove_file(&path).map_err(|e| AppError::io(e, "delete message", &path))?;
+    let _ = fs::remove_file(path.with_extension("json"));
+    Ok(())
+}
+
+#[command]
+pub fn clear_mail(project_path: String, state: State<'_, MailCatcher>) -> Result<(), AppError> {
+    let dir = state.project_dir(&project_path);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| AppError::io(e, "clear mail", &dir))?;
+    }
+    Ok(())
+}
@@ -5,33 +5,137 @@ mod commands;
 mod utils;
 mod project_manager;
 mod manage_packages;
-use std::collections::HashMap;
-use std::sync::Mutex;
-use crate::project_manager::ProjectManager;
+mod node_manager;
+mod process_logs;
+mod log_parser;
+mod port_manager;
+mod proxy;
+mod certificates;
+mod mail_catcher;
+mod env_manager;
+mod secrets;
+mod project_registry;
+mod error;
+mod frameworks;
+mod binaries;
+mod workspaces;
+mod package_managers;
+use crate::project_manager::{ProjectManager, DEFAULT_GRACE_PERIOD};
+use crate::node_manager::NodeManager;
+use crate::process_logs::LogStore;
+use crate::port_manager::PortAllocator;
+use crate::proxy::ProxyServer;
+use crate::certificates::CertificateStore;
+use crate::mail_catcher::MailCatcher;
+use crate::env_manager::EnvManager;
+use crate::secrets::SecretStore;
+use crate::project_registry::ProjectRegistry;
+use std::sync::Arc;
+use tauri::{Manager, RunEvent};
 
 fn main() {
     let _ = fix_path_env::fix(); // to get the PATH environment variable
     tauri::Builder::default()
-        .manage(ProjectManager(Mutex::new(HashMap::new()))) // Manage the state within Tauri
         .invoke_handler(tauri::generate_handler![
             commands::create_local_projects_folder,
             commands::start_project_creation,
             commands::launch_ide,
             commands::open_file_explorer,
-            commands::detect_runtime,
+            package_managers::detect_runtime,
             commands::analyze_project,
             commands::start_project,
             commands::close_project,
+            commands::list_scripts,
+            commands::run_script,
+            project_manager::list_processes,
+            project_manager::start_process,
+            project_manager::stop_process,
+            project_manager::restart_process,
+            project_manager::set_restart_policy,
+            project_manager::get_restart_status,
+            project_manager::list_orphan_processes,
+            project_manager::handle_orphan_processes,
+            process_logs::get_log_tail,
+            process_logs::search_logs,
+            process_logs::export_logs,
+            port_manager::list_port_assignments,
+            port_manager::reserve_port,
+            port_manager::release_port,
+            port_manager::get_port_owner,
+            proxy::get_proxy_status,
+            proxy::configure_proxy,
+            certificates::get_certificate_authority,
+            certificates::export_ca_certificate,
+            certificates::get_project_certificate,
+            certificates::regenerate_project_certificate,
+            certificates::revoke_project_certificate,
+            mail_catcher::get_mail_server,
+            mail_catcher::list_mail,
+            mail_catcher::get_mail,
+            mail_catcher::save_mail_attachment,
+            mail_catcher::delete_mail,
+            mail_catcher::clear_mail,
+            env_manager::list_env_files,
+            env_manager::get_effective_env,
+            env_manager::set_env_var,
+            env_manager::delete_env_var,
+            env_manager::set_env_override,
+            secrets::get_secrets_status,
+            secrets::unlock_secrets,
+            secrets::lock_secrets,
+            secrets::list_secrets,
+            secrets::set_secret,
+            secrets::delete_secret,
+            secrets::mark_env_secret,
+            secrets::export_secrets,
+            secrets::import_secrets,
+            project_registry::add_project,
+            project_registry::list_projects,
+            project_registry::get_project,
+            project_registry::open_project,
+            project_registry::rename_project,
+            project_registry::set_project_tags,
+            project_registry::relocate_project,
+            project_registry::forget_project,
+            project_registry::scan_for_projects,
+            workspaces::get_workspace,
+            workspaces::start_workspace_apps,
             manage_packages::install_dependency,
             manage_packages::update_dependency,
             manage_packages::delete_dependency,
             manage_packages::reinstall_dependencies,
             commands::update_project_path,
             commands::delete_site,
+            node_manager::list_node_versions,
+            node_manager::install_node_from_file,
+            node_manager::remove_node_version,
+            node_manager::select_node_version,
+            node_manager::resolve_node_version,
+            node_manager::get_project_node_version,
         ])
-        .setup(|_app| 
+        .setup(|app| {
+            let data_dir = app.path_resolver().app_data_dir().ok_or("Could not determine app data directory")?;
+            app.manage(ProjectManager::load(&data_dir)); // Manage the state within Tauri
+            app.manage(NodeManager::load(&data_dir));
+            app.manage(LogStore::new(data_dir.join("logs")));
+            app.manage(PortAllocator::load(&data_dir));
+            let certificates = Arc::new(CertificateStore::load(&data_dir));
+            let proxy = ProxyServer::load(&data_dir, certificates.clone());
+            proxy.start();
+            app.manage(certificates);
+            app.manage(proxy);
+            app.manage(MailCatcher::new(data_dir.join("mail")));
+            app.manage(EnvManager::load(&data_dir));
+            app.manage(SecretStore::new(&data_dir));
+            app.manage(ProjectRegistry::load(&data_dir));
             Ok(())
-        )
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        })
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            // don't leave dev servers running after dev-thing quits
+            if let RunEvent::Exit = event {
+                app_handle.state::<ProjectManager>().stop_all(DEFAULT_GRACE_PERIOD);
+            }
+        });
 }
\ No newline at end of file
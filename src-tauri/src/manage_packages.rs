@@ -1,30 +1,177 @@
-use tauri::{command, Window};
+use tauri::{command, State, Window};
+use std::collections::HashMap;
+use std::process::Output;
 use std::thread;
-use crate::utils::execute_command;
+use crate::utils::{resolve_executable, Argv};
+use crate::node_manager::NodeManager;
+use crate::env_manager::EnvManager;
+use crate::secrets::{mask_secrets, SecretStore};
+use crate::error::{AppError, ErrorCode};
+use crate::workspaces::find_member;
+
+const MAX_PACKAGE_NAME_LENGTH: usize = 214;
+
+// npm's naming rules: an optional @scope/, then url safe characters, not
+// starting with . or _ (or -, which would be read as a flag). Capitals are
+// allowed since older packages still have them (e.g. JSONStream) even
+// though new ones can't
+pub fn validate_package_name(name: &str) -> Result<(), AppError> {
+    let invalid = |reason: &str| {
+        AppError::new(ErrorCode::InvalidInput, format!("Invalid package name \"{}\": {}", name, reason))
+            .with_hint("Package names look like `lodash` or `@scope/name`")
+    };
+    if name.is_empty() {
+        return Err(invalid("it is empty"));
+    }
+    if name.len() > MAX_PACKAGE_NAME_LENGTH {
+        return Err(invalid("it is longer than 214 characters"));
+    }
+    let bare = match name.strip_prefix('@') {
+        Some(scoped) => {
+            let (scope, bare) = scoped.split_once('/').ok_or_else(|| invalid("a scoped name needs a `/`"))?;
+            if !is_url_safe(scope) {
+                return Err(invalid("the scope contains characters that aren't allowed"));
+            }
+            bare
+        }
+        None => name,
+    };
+    if bare.starts_with(['.', '_', '-']) {
+        return Err(invalid("it can't start with ., _ or -"));
+    }
+    if !is_url_safe(bare) {
+        return Err(invalid("it contains characters that aren't allowed"));
+    }
+    Ok(())
+}
+
+fn is_url_safe(part: &str) -> bool {
+    !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c))
+}
+
+// a version, range or dist-tag (1.2.3, ^1.2, >=1 <2, next). urls, git and
+// file specs are left to package.json on purpose
+pub fn validate_version(version: &str) -> Result<(), AppError> {
+    let allowed = version.chars().all(|c| c.is_ascii_alphanumeric() || ".-+^~<>=|* ".contains(c));
+    if version.trim().is_empty() || version.starts_with('-') || !allowed {
+        return Err(AppError::new(ErrorCode::InvalidInput, format!("Invalid version \"{}\"", version))
+            .with_hint("Use a version, a range like ^1.2.0 or a tag like latest"));
+    }
+    Ok(())
+}
+
+// `name` or `name@version`, validated
+fn package_spec(dependency: &str, version: Option<&str>) -> Result<String, AppError> {
+    validate_package_name(dependency)?;
+    match version {
+        Some(version) => {
+            validate_version(version)?;
+            Ok(format!("{}@{}", dependency, version.trim()))
+        }
+        None => Ok(dependency.to_string()),
+    }
+}
+
+// the dialogs send "" when the version field is left blank, meaning latest
+fn requested_version(version: &Option<String>) -> Option<&str> {
+    version.as_deref().map(str::trim).filter(|version| !version.is_empty())
+}
+
+// environment to run the package manager with, plus the secret values to
+// mask in its output; fails up front when it isn't installed rather than
+// with a shell error in the status event
+fn package_manager_env(
+    runtime: &str,
+    project_path: &str,
+    node_state: &NodeManager,
+    env_state: &EnvManager,
+    secret_state: &SecretStore,
+) -> Result<(HashMap<String, String>, Vec<String>), AppError> {
+    let mut envs = env_state.project_env(project_path)?;
+    // postinstall scripts may need them as much as the dev server does
+    let masked = secret_state.inject(project_path, &mut envs)?;
+    envs.extend(node_state.command_env(project_path)?);
+    resolve_executable(runtime, project_path, &envs).map_err(|_| AppError::package_manager_missing(runtime))?;
+    Ok((envs, masked))
+}
+
+fn masked_output(mut output: Output, masked: &[String]) -> Output {
+    output.stdout = mask_secrets(&String::from_utf8_lossy(&output.stdout), masked).into_bytes();
+    output.stderr = mask_secrets(&String::from_utf8_lossy(&output.stderr), masked).into_bytes();
+    output
+}
+
+// `<runtime> <args>`, for a workspace member through the package manager's
+// filter so the change lands in that package's package.json
+fn package_command(runtime: &str, project_path: &str, workspace_package: Option<&str>, args: &[&str]) -> Result<Argv, AppError> {
+    let member = match workspace_package {
+        Some(package) => {
+            let member = find_member(project_path, package)?;
+            let name = member.name.ok_or_else(|| {
+                AppError::new(ErrorCode::InvalidInput, format!("Workspace package {} has no name in its package.json", package))
+            })?;
+            Some((name, member.path))
+        }
+        None => None,
+    };
+    Ok(match (runtime, member) {
+        (_, None) => Argv::new(runtime).args(args.iter().copied()),
+        ("pnpm", Some((name, _))) => Argv::new("pnpm").arg("--filter").arg(name).args(args.iter().copied()),
+        ("npm", Some((name, _))) => Argv::new("npm").args(args.iter().copied()).arg("--workspace").arg(name),
+        ("yarn", Some((name, _))) => Argv::new("yarn").arg("workspace").arg(name).args(args.iter().copied()),
+        // bun has no filter for add / remove, it runs them in the member's folder
+        ("bun", Some((_, path))) => Argv::new("bun").args(args.iter().copied()).arg("--cwd").arg(path),
+        ("deno", Some(_)) => {
+            return Err(AppError::new(ErrorCode::InvalidInput, "deno can't change a workspace member's dependencies from the root")
+                .with_hint("Open the member folder as its own project"))
+        }
+        _ => return Err(AppError::unsupported_runtime(runtime)),
+    })
+}
+
+// deno adds packages from npm unless they're given as `jsr:@scope/name`
+fn deno_spec(dependency: &str, version: Option<&str>) -> Result<String, AppError> {
+    match dependency.strip_prefix("jsr:") {
+        Some(name) => Ok(format!("jsr:{}", package_spec(name, version)?)),
+        None => Ok(format!("npm:{}", package_spec(bare_name(dependency), version)?)),
+    }
+}
+
+// the name without deno's npm: / jsr: prefix
+fn bare_name(dependency: &str) -> &str {
+    dependency.strip_prefix("npm:").or_else(|| dependency.strip_prefix("jsr:")).unwrap_or(dependency)
+}
+
 //install, update, delete, reinstall dependencies
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub fn install_dependency(
     window: Window,
+    node_state: State<'_, NodeManager>,
+    env_state: State<'_, EnvManager>,
+    secret_state: State<'_, SecretStore>,
     project_path: String,
     runtime: String,
     dependency: String,
     version: Option<String>,
-) -> Result<(), String> {
-    let versioned_dependency = if let Some(ver) = version {
-        format!("{}@{}", dependency, ver)
-    } else {
-        dependency
-    };
+    workspace_package: Option<String>,
+) -> Result<(), AppError> {
+    let version = requested_version(&version);
+    let versioned_dependency = package_spec(bare_name(&dependency), version)?;
+    let member = workspace_package.as_deref();
     let cmd = match runtime.as_str() {
-        "pnpm" => format!("pnpm add {}", versioned_dependency),
-        "npm" => format!("npm install {}", versioned_dependency),
-        "yarn" => format!("yarn add {}", versioned_dependency),
-        _ => return Err("Unsupported runtime".to_string()),
+        "pnpm" => package_command("pnpm", &project_path, member, &["add", &versioned_dependency])?,
+        "npm" => package_command("npm", &project_path, member, &["install", &versioned_dependency])?,
+        "yarn" => package_command("yarn", &project_path, member, &["add", &versioned_dependency])?,
+        "bun" => package_command("bun", &project_path, member, &["add", &versioned_dependency])?,
+        "deno" => package_command("deno", &project_path, member, &["add", &deno_spec(&dependency, version)?])?,
+        _ => return Err(AppError::unsupported_runtime(&runtime)),
     };
+    let (envs, masked) = package_manager_env(&runtime, &project_path, &node_state, &env_state, &secret_state)?;
     println!("Executing command: {}", cmd);
     thread::spawn(move || {
         window.emit("install_status", "Installing dependency...").unwrap();
-        let output = execute_command(&[&cmd], &project_path);
+        let output = cmd.output(&project_path, &envs).map(|output| masked_output(output, &masked));
        
         match output {
             Ok(output) => {
@@ -56,33 +203,47 @@ pub fn install_dependency(
 }
 
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub fn update_dependency(
     window: Window,
+    node_state: State<'_, NodeManager>,
+    env_state: State<'_, EnvManager>,
+    secret_state: State<'_, SecretStore>,
     project_path: String,
     runtime: String,
     dependency: String,
     version: Option<String>,
-) -> Result<(), String> {
-    let cmd = if let Some(ver) = version {
+    workspace_package: Option<String>,
+) -> Result<(), AppError> {
+    let member = workspace_package.as_deref();
+    let cmd = if let Some(version) = requested_version(&version) {
+        let versioned_dependency = package_spec(bare_name(&dependency), Some(version))?;
         match runtime.as_str() {
-            "pnpm" => format!("pnpm add {}@{}", dependency, ver),
-            "npm" => format!("npm install {}@{}", dependency, ver),
-            "yarn" => format!("yarn add {}@{}", dependency, ver),
-            _ => return Err("Unsupported runtime".to_string()),
+            "pnpm" => package_command("pnpm", &project_path, member, &["add", &versioned_dependency])?,
+            "npm" => package_command("npm", &project_path, member, &["install", &versioned_dependency])?,
+            "yarn" => package_command("yarn", &project_path, member, &["add", &versioned_dependency])?,
+            "bun" => package_command("bun", &project_path, member, &["add", &versioned_dependency])?,
+            "deno" => package_command("deno", &project_path, member, &["add", &deno_spec(&dependency, Some(version))?])?,
+            _ => return Err(AppError::unsupported_runtime(&runtime)),
         }
     } else {
+        let dependency = bare_name(&dependency).to_string();
+        validate_package_name(&dependency)?;
         match runtime.as_str() {
-            "pnpm" => format!("pnpm update {}", dependency),
-            "npm" => format!("npm update {}", dependency),
-            "yarn" => format!("yarn upgrade {}", dependency),
-            _ => return Err("Unsupported runtime".to_string()),
+            "pnpm" => package_command("pnpm", &project_path, member, &["update", &dependency])?,
+            "npm" => package_command("npm", &project_path, member, &["update", &dependency])?,
+            "yarn" => package_command("yarn", &project_path, member, &["upgrade", &dependency])?,
+            "bun" => package_command("bun", &project_path, member, &["update", &dependency])?,
+            "deno" => package_command("deno", &project_path, member, &["outdated", "--update", &dependency])?,
+            _ => return Err(AppError::unsupported_runtime(&runtime)),
         }
     };
 
+    let (envs, masked) = package_manager_env(&runtime, &project_path, &node_state, &env_state, &secret_state)?;
     println!("Executing command: {}", cmd);
     thread::spawn(move || {
         window.emit("update_status", "Updating dependency...").unwrap();
-        let output = execute_command(&[&cmd], &project_path);
+        let output = cmd.output(&project_path, &envs).map(|output| masked_output(output, &masked));
         match output {
             Ok(output) => {
                 println!("Command executed. Exit status: {}", output.status);
@@ -113,22 +274,33 @@ pub fn update_dependency(
 }
 
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub fn delete_dependency(
     window: Window,
+    node_state: State<'_, NodeManager>,
+    env_state: State<'_, EnvManager>,
+    secret_state: State<'_, SecretStore>,
     project_path: String,
     runtime: String,
     dependency: String,
-) -> Result<(), String> {
+    workspace_package: Option<String>,
+) -> Result<(), AppError> {
+    let dependency = bare_name(&dependency).to_string();
+    validate_package_name(&dependency)?;
+    let member = workspace_package.as_deref();
     let cmd = match runtime.as_str() {
-        "pnpm" => format!("pnpm remove {}", dependency),
-        "npm" => format!("npm uninstall {}", dependency),
-        "yarn" => format!("yarn remove {}", dependency),
-        _ => return Err("Unsupported runtime".to_string()),
+        "pnpm" => package_command("pnpm", &project_path, member, &["remove", &dependency])?,
+        "npm" => package_command("npm", &project_path, member, &["uninstall", &dependency])?,
+        "yarn" => package_command("yarn", &project_path, member, &["remove", &dependency])?,
+        "bun" => package_command("bun", &project_path, member, &["remove", &dependency])?,
+        "deno" => package_command("deno", &project_path, member, &["remove", &dependency])?,
+        _ => return Err(AppError::unsupported_runtime(&runtime)),
     };
+    let (envs, masked) = package_manager_env(&runtime, &project_path, &node_state, &env_state, &secret_state)?;
     println!("Executing command: {}", cmd);
     thread::spawn(move || {
         window.emit("delete_status", "Deleting dependency...").unwrap();
-        let output = execute_command(&[&cmd], &project_path);
+        let output = cmd.output(&project_path, &envs).map(|output| masked_output(output, &masked));
         match output {
             Ok(output) => {
                 println!("Command executed. Exit status: {}", output.status);
@@ -161,19 +333,26 @@ pub fn delete_dependency(
 #[command]
 pub fn reinstall_dependencies(
     window: Window,
+    node_state: State<'_, NodeManager>,
+    env_state: State<'_, EnvManager>,
+    secret_state: State<'_, SecretStore>,
     project_path: String,
     runtime: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let cmd = match runtime.as_str() {
-        "pnpm" => "pnpm install --force".to_string(),
-        "npm" => "npm install --force".to_string(),
-        "yarn" => "yarn install --force".to_string(),
-        _ => return Err("Unsupported runtime".to_string()),
+        "pnpm" => Argv::new("pnpm").args(["install", "--force"]),
+        "npm" => Argv::new("npm").args(["install", "--force"]),
+        "yarn" => Argv::new("yarn").args(["install", "--force"]),
+        "bun" => Argv::new("bun").args(["install", "--force"]),
+        // --reload fetches everything again instead of using deno's cache
+        "deno" => Argv::new("deno").args(["install", "--reload"]),
+        _ => return Err(AppError::unsupported_runtime(&runtime)),
     };
+    let (envs, masked) = package_manager_env(&runtime, &project_path, &node_state, &env_state, &secret_state)?;
     println!("Executing command: {}", cmd);
     thread::spawn(move || {
         window.emit("reinstall_status", "Reinstalling dependencies...").unwrap();
-        let output = execute_command(&[&cmd], &project_path);
+        let output = cmd.output(&project_path, &envs).map(|output| masked_output(output, &masked));
         match output {
             Ok(output) => {
                 println!("Command executed. Exit status: {}", output.status);
@@ -202,3 +381,24 @@ pub fn reinstall_dependencies(
     });
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_version_installs_latest() {
+        for blank in ["", "   "] {
+            let version = Some(blank.to_string());
+            assert_eq!(requested_version(&version), None);
+            assert_eq!(package_spec("lodash", requested_version(&version)).unwrap(), "lodash");
+        }
+        assert_eq!(requested_version(&None), None);
+    }
+
+    #[test]
+    fn version_is_trimmed() {
+        let version = Some(" ^4.17.0 ".to_string());
+        assert_eq!(package_spec("lodash", requested_version(&version)).unwrap(), "lodash@^4.17.0");
+    }
+}
@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use semver::{Version, VersionReq};
+use tauri::{command, State};
+use tauri::api::path::home_dir;
+use crate::error::{AppError, ErrorCode};
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NodeInstall {
+    pub version: String,
+    // where the install was found: system, nvm, fnm, volta, asdf or dev-thing
+    pub source: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NodeRequirement {
+    pub spec: String,
+    // the file the requirement was read from
+    pub source: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NodeResolution {
+    pub required: Option<NodeRequirement>,
+    pub resolved: Option<NodeInstall>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct NodeVersionMismatch {
+    pub required: String,
+    pub source: String,
+    pub available: Vec<String>,
+    pub message: String,
+}
+
+// installs managed by dev-thing live in `install_dir/<version>`,
+// per-project selections are persisted to `pins_file` as { project_path: version }
+pub struct NodeManager {
+    pub install_dir: PathBuf,
+    pub pins_file: PathBuf,
+    pub pins: Mutex<HashMap<String, String>>,
+}
+
+impl NodeManager {
+    pub fn load(data_dir: &Path) -> NodeManager {
+        let pins_file = data_dir.join("node-pins.json");
+        let pins = fs::read_to_string(&pins_file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        NodeManager {
+            install_dir: data_dir.join("node"),
+            pins_file,
+            pins: Mutex::new(pins),
+        }
+    }
+
+    fn save_pins(&self, pins: &HashMap<String, String>) -> Result<(), AppError> {
+        if let Some(parent) = self.pins_file.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(e, "create data directory", parent))?;
+        }
+        let content = serde_json::to_string_pretty(pins).map_err(|e| e.to_string())?;
+        fs::write(&self.pins_file, content).map_err(|e| AppError::io(e, "save node versions", &self.pins_file))
+    }
+
+    pub fn rename_project(&self, old: &str, new: &str) -> Result<(), AppError> {
+        let mut pins = self.pins.lock().unwrap();
+        if let Some(version) = pins.remove(old) {
+            pins.insert(new.to_string(), version);
+            self.save_pins(&pins)?;
+        }
+        Ok(())
+    }
+
+    // every node toolchain we can find, newest first
+    pub fn discover(&self) -> Vec<NodeInstall> {
+        let mut installs = Vec::new();
+
+        if let Ok(system_node) = which::which("node") {
+            if let Some(version) = node_binary_version(&system_node) {
+                installs.push(NodeInstall {
+                    version,
+                    source: "system".to_string(),
+                    path: system_node.to_string_lossy().to_string(),
+                });
+            }
+        }
+
+        for (source, root, layout) in version_manager_roots() {
+            installs.extend(scan_version_dirs(&root, source, layout));
+        }
+        installs.extend(scan_version_dirs(&self.install_dir, "dev-thing", InstallLayout::Plain));
+
+        // the system node is often just an nvm/volta binary on PATH
+        let mut seen = Vec::new();
+        installs.retain(|install| {
+            let canonical = fs::canonicalize(&install.path).unwrap_or_else(|_| PathBuf::from(&install.path));
+            if seen.contains(&canonical) {
+                false
+            } else {
+                seen.push(canonical);
+                true
+            }
+        });
+
+        installs.sort_by(|a, b| compare_versions(&b.version, &a.version));
+        installs
+    }
+
+    pub fn pinned_install(&self, project_path: &str) -> Result<Option<NodeInstall>, AppError> {
+        let pinned = self.pins.lock().unwrap().get(project_path).cloned();
+        match pinned {
+            Some(version) => self.discover().into_iter().find(|install| install.version == version).map(Some).ok_or_else(|| {
+                AppError::new(ErrorCode::NotFound, format!("Node {} is selected for this project but is no longer installed", version))
+                    .with_hint("Install it again or select another Node version for the project")
+            }),
+            None => Ok(None),
+        }
+    }
+
+    // a version selected in dev-thing wins over whatever the project declares,
+    // otherwise the declared requirement is matched against the local installs
+    pub fn resolve(&self, project_path: &str) -> Result<NodeResolution, AppError> {
+        let required = read_node_requirement(project_path);
+        if let Some(pinned) = self.pinned_install(project_path)? {
+            return Ok(NodeResolution { required, resolved: Some(pinned) });
+        }
+
+        let resolved = match &required {
+            Some(requirement) => resolve_requirement(&requirement.spec, &self.discover()).map_err(|e| {
+                e.with_hint(format!("Fix the Node version in {}", requirement.source)).with_context(requirement.source.clone())
+            })?,
+            None => None,
+        };
+        Ok(NodeResolution { required, resolved })
+    }
+
+    // like `resolve`, but a declared requirement nothing satisfies is an error
+    pub fn project_node(&self, project_path: &str) -> Result<Option<NodeInstall>, AppError> {
+        let resolution = self.resolve(project_path)?;
+
+        match (resolution.required, resolution.resolved) {
+            (_, Some(install)) => Ok(Some(install)),
+            (None, None) => Ok(None),
+            (Some(requirement), None) => {
+                let available: Vec<String> = self.discover().into_iter().map(|install| install.version).collect();
+                let message = if available.is_empty() {
+                    format!("Project requires Node {} ({}) but no Node installs were found", requirement.spec, requirement.source)
+                } else {
+                    format!(
+                        "Project requires Node {} ({}) but only {} installed",
+                        requirement.spec,
+                        requirement.source,
+                        available.join(", ")
+                    )
+                };
+                Err(NodeVersionMismatch {
+                    required: requirement.spec,
+                    source: requirement.source,
+                    available,
+                    message,
+                }
+                .into())
+            }
+        }
+    }
+
+    // environment to run project commands with, so that `node`, `npm`, `npx`
+    // etc. resolve to the project's node before anything else on PATH.
+    // Falls back to PATH as is when the project's requirement can't be met,
+    // start_dev_server checks it with project_node before getting here.
+    pub fn command_env(&self, project_path: &str) -> Result<HashMap<String, String>, AppError> {
+        let mut envs = HashMap::new();
+        let resolved = match self.resolve(project_path) {
+            Ok(node) => node.resolved,
+            Err(e) => {
+                println!("Running {} with node from PATH: {}", project_path, e);
+                None
+            }
+        };
+        if let Some(install) = resolved {
+            envs.insert("PATH".to_string(), path_with_node(Path::new(&install.path))?);
+        }
+        Ok(envs)
+    }
+}
+
+// .nvmrc > .node-version > .tool-versions > package.json engines.node
+pub fn read_node_requirement(project_path: &str) -> Option<NodeRequirement> {
+    let root = Path::new(project_path);
+
+    for file in [".nvmrc", ".node-version"] {
+        if let Ok(content) = fs::read_to_string(root.join(file)) {
+            let spec = content
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or("").trim())
+                .find(|line| !line.is_empty());
+            if let Some(spec) = spec {
+                return Some(NodeRequirement { spec: spec.to_string(), source: file.to_string() });
+            }
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(root.join(".tool-versions")) {
+        for line in content.lines() {
+            let mut parts = line.split('#').next().unwrap_or("").split_whitespace();
+            if matches!(parts.next(), Some("nodejs") | Some("node")) {
+                if let Some(spec) = parts.next() {
+                    return Some(NodeRequirement { spec: spec.to_string(), source: ".tool-versions".to_string() });
+                }
+            }
+        }
+    }
+
+    let package_json: Value = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())?;
+    package_json["engines"]["node"].as_str().map(|spec| NodeRequirement {
+        spec: spec.trim().to_string(),
+        source: "package.json engines".to_string(),
+    })
+}
+
+// highest install satisfying the spec; installs are expected newest first
+pub fn resolve_requirement(spec: &str, installs: &[NodeInstall]) -> Result<Option<NodeInstall>, AppError> {
+    let spec = spec.trim();
+    if spec == "system" {
+        return Ok(installs.iter().find(|install| install.source == "system").cloned());
+    }
+
+    let matcher = NodeSpec::parse(spec)?;
+    Ok(installs
+        .iter()
+        .find(|install| Version::parse(&install.version).map(|v| matcher.matches(&v)).unwrap_or(false))
+        .cloned())
+}
+
+// release lines that reached LTS; an even major only gets its codename
+// months after its release, so `lts/*` can't just match even majors.
+// Needs a new entry each October
+const LTS_CODENAMES: [(&str, u64); 11] = [
+    ("argon", 4),
+    ("boron", 6),
+    ("carbon", 8),
+    ("dubnium", 10),
+    ("erbium", 12),
+    ("fermium", 14),
+    ("gallium", 16),
+    ("hydrogen", 18),
+    ("iron", 20),
+    ("jod", 22),
+    ("krypton", 24),
+];
+
+enum NodeSpec {
+    Any,
+    Lts,
+    Major(u64),
+    Ranges(Vec<VersionReq>),
+}
+
+impl NodeSpec {
+    fn parse(spec: &str) -> Result<NodeSpec, AppError> {
+        let lower = spec.to_lowercase();
+        match lower.as_str() {
+            "node" | "latest" | "current" | "stable" | "*" | "" => return Ok(NodeSpec::Any),
+            "lts/*" | "lts" => return Ok(NodeSpec::Lts),
+            _ => {}
+        }
+        if let Some(codename) = lower.strip_prefix("lts/") {
+            return lts_major(codename)
+                .map(NodeSpec::Major)
+                .ok_or_else(|| AppError::new(ErrorCode::InvalidInput, format!("Unknown Node LTS codename: {}", codename)));
+        }
+
+        spec.split("||")
+            .map(|range| npm_range_to_req(range.trim()))
+            .collect::<Result<Vec<_>, _>>()
+            .map(NodeSpec::Ranges)
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        match self {
+            NodeSpec::Any => true,
+            NodeSpec::Lts => LTS_CODENAMES.iter().any(|(_, major)| *major == version.major),
+            NodeSpec::Major(major) => version.major == *major,
+            NodeSpec::Ranges(ranges) => ranges.iter().any(|range| range.matches(version)),
+        }
+    }
+}
+
+fn lts_major(codename: &str) -> Option<u64> {
+    LTS_CODENAMES.iter().find(|(name, _)| *name == codename).map(|(_, major)| *major)
+}
+
+// npm ranges separate comparators with spaces and treat bare versions as
+// exact (`20.11.1`) or partial (`20`, `20.11`); the semver crate wants commas
+// and reads bare versions as caret requirements
+fn npm_range_to_req(range: &str) -> Result<VersionReq, AppError> {
+    let invalid = |_| AppError::new(ErrorCode::InvalidInput, format!("Invalid Node version requirement: {}", range));
+
+    if let Some((low, high)) = range.split_once(" - ") {
+        let req = format!(">={}, <={}", low.trim().trim_start_matches('v'), high.trim().trim_start_matches('v'));
+        return VersionReq::parse(&req).map_err(invalid);
+    }
+
+    let mut comparators = Vec::new();
+    let mut pending_op = String::new();
+    for token in range.split_whitespace() {
+        if token.chars().all(|c| "<>=~^".contains(c)) {
+            pending_op.push_str(token);
+            continue;
+        }
+        let token = format!("{}{}", pending_op, token);
+        pending_op.clear();
+
+        let op_len = token.find(|c: char| !"<>=~^".contains(c)).unwrap_or(token.len());
+        let (op, version) = token.split_at(op_len);
+        let version = version.trim_start_matches('v');
+        let op = if !op.is_empty() || version.contains(['x', 'X', '*']) {
+            op.to_string()
+        } else if version.split('.').count() == 3 {
+            "=".to_string()
+        } else {
+            "~".to_string()
+        };
+        comparators.push(format!("{}{}", op, version));
+    }
+
+    if comparators.is_empty() {
+        return Ok(VersionReq::STAR);
+    }
+    VersionReq::parse(&comparators.join(", ")).map_err(invalid)
+}
+
+pub fn path_with_node(node_binary: &Path) -> Result<String, AppError> {
+    let bin_dir = node_binary
+        .parent()
+        .ok_or_else(|| AppError::new(ErrorCode::InvalidInput, "Invalid node binary path").with_context(node_binary.display().to_string()))?;
+    let mut paths = vec![bin_dir.to_path_buf()];
+    if let Some(current) = env::var_os("PATH") {
+        paths.extend(env::split_paths(&current));
+    }
+    let joined = env::join_paths(paths).map_err(|e| e.to_string())?;
+    Ok(joined.to_string_lossy().to_string())
+}
+
+#[derive(Clone, Copy)]
+enum InstallLayout {
+    // <root>/<version>/bin/node
+    Plain,
+    // <root>/<version>/installation/bin/node (fnm)
+    Installation,
+}
+
+fn version_manager_roots() -> Vec<(&'static str, PathBuf, InstallLayout)> {
+    let mut roots = Vec::new();
+    let home = match home_dir() {
+        Some(home) => home,
+        None => return roots,
+    };
+
+    let nvm_dir = env::var_os("NVM_DIR").map(PathBuf::from).unwrap_or_else(|| home.join(".nvm"));
+    roots.push(("nvm", nvm_dir.join("versions").join("node"), InstallLayout::Plain));
+    if let Some(nvm_home) = env::var_os("NVM_HOME") {
+        // nvm-windows keeps versions directly in NVM_HOME
+        roots.push(("nvm", PathBuf::from(nvm_home), InstallLayout::Plain));
+    }
+
+    let fnm_dir = env::var_os("FNM_DIR").map(PathBuf::from).unwrap_or_else(|| {
+        if cfg!(target_os = "macos") {
+            home.join("Library").join("Application Support").join("fnm")
+        } else if cfg!(target_os = "windows") {
+            env::var_os("APPDATA").map(PathBuf::from).unwrap_or_else(|| home.clone()).join("fnm")
+        } else {
+            home.join(".local").join("share").join("fnm")
+        }
+    });
+    roots.push(("fnm", fnm_dir.join("node-versions"), InstallLayout::Installation));
+
+    let volta_home = env::var_os("VOLTA_HOME").map(PathBuf::from).unwrap_or_else(|| {
+        if cfg!(target_os = "windows") {
+            env::var_os("LOCALAPPDATA").map(PathBuf::from).unwrap_or_else(|| home.clone()).join("Volta")
+        } else {
+            home.join(".volta")
+        }
+    });
+    roots.push(("volta", volta_home.join("tools").join("image").join("node"), InstallLayout::Plain));
+
+    let asdf_dir = env::var_os("ASDF_DATA_DIR").map(PathBuf::from).unwrap_or_else(|| home.join(".asdf"));
+    roots.push(("asdf", asdf_dir.join("installs").join("nodejs"), InstallLayout::Plain));
+
+    roots
+}
+
+fn scan_version_dirs(root: &Path, source: &str, layout: InstallLayout) -> Vec<NodeInstall> {
+    let mut installs = Vec::new();
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(_) => return installs,
+    };
+
+    for entry in entries.flatten() {
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        let version = dir_name.trim_start_matches('v');
+        if Version::parse(version).is_err() {
+            continue;
+        }
+        let install_root = match layout {
+            InstallLayout::Plain => entry.path(),
+            InstallLayout::Installation => entry.path().join("installation"),
+        };
+        let binary = node_binary_in(&install_root);
+        if binary.exists() {
+            installs.push(NodeInstall {
+                version: version.to_string(),
+                source: source.to_string(),
+                path: binary.to_string_lossy().to_string(),
+            });
+        }
+    }
+    installs
+}
+
+fn node_binary_in(dir: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        dir.join("node.exe")
+    } else {
+        dir.join("bin").join("node")
+    }
+}
+
+pub fn node_binary_version(binary: &Path) -> Option<String> {
+    let mut command = Command::new(binary);
+    command.arg("--version");
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().trim_start_matches('v').to_string();
+    Version::parse(&version).ok().map(|_| version)
+}
+
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    match (Version::parse(a), Version::parse(b)) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+fn extract_archive(archive: &Path, dest: &Path) -> Result<(), AppError> {
+    let file = fs::File::open(archive).map_err(|e| AppError::io(e, "open the archive", archive))?;
+    let file_name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        tar::Archive::new(flate2::read::GzDecoder::new(file))
+            .unpack(dest)
+            .map_err(|e| AppError::io(e, "extract archive", archive))
+    } else if file_name.ends_with(".tar.xz") {
+        tar::Archive::new(xz2::read::XzDecoder::new(file))
+            .unpack(dest)
+            .map_err(|e| AppError::io(e, "extract archive", archive))
+    } else if file_name.ends_with(".zip") {
+        zip::ZipArchive::new(file).and_then(|mut zip| zip.extract(dest)).map_err(|e| {
+            AppError::new(ErrorCode::InvalidInput, format!("Failed to extract archive: {}", e)).with_context(archive.display().to_string())
+        })
+    } else {
+        Err(AppError::new(ErrorCode::InvalidInput, "Unsupported archive format")
+            .with_hint("Use a .tar.gz, .tar.xz or .zip archive from nodejs.org")
+            .with_context(archive.display().to_string()))
+    }
+}
+
+// official archives contain a single `node-v<version>-<platform>` folder
+fn find_extracted_root(dir: &Path) -> Option<PathBuf> {
+    if node_binary_in(dir).exists() {
+        return Some(dir.to_path_buf());
+    }
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.is_dir() && node_binary_in(path).exists())
+}
+
+#[command]
+pub fn list_node_versions(state: State<'_, NodeManager>) -> Result<Vec<NodeInstall>, AppError> {
+    Ok(state.discover())
+}
+
+#[command]
+pub fn install_node_from_file(archive_path: String, state: State<'_, NodeManager>) -> Result<NodeInstall, AppError> {
+    fs::create_dir_all(&state.install_dir).map_err(|e| AppError::io(e, "create node directory", &state.install_dir))?;
+    let staging_dir = state
+        .install_dir
+        .join(format!(".extract-{}", chrono::Local::now().timestamp_millis()));
+
+    let result = (|| {
+        extract_archive(Path::new(&archive_path), &staging_dir)?;
+        let root = find_extracted_root(&staging_dir).ok_or_else(|| {
+            AppError::new(ErrorCode::InvalidInput, "Archive does not contain a node distribution").with_context(archive_path.clone())
+        })?;
+        let version = node_binary_version(&node_binary_in(&root)).ok_or_else(|| {
+            AppError::new(ErrorCode::InvalidInput, "The node binary in this archive cannot run on this system")
+                .with_hint("Download the archive for this operating system and architecture")
+                .with_context(archive_path.clone())
+        })?;
+
+        let target = state.install_dir.join(&version);
+        if target.exists() {
+            return Err(AppError::new(ErrorCode::AlreadyExists, format!("Node {} is already installed", version)));
+        }
+        fs::rename(&root, &target).map_err(|e| AppError::io(e, &format!("install node {}", version), &target))?;
+
+        Ok(NodeInstall {
+            version,
+            source: "dev-thing".to_string(),
+            path: node_binary_in(&target).to_string_lossy().to_string(),
+        })
+    })();
+
+    let _ = fs::remove_dir_all(&staging_dir);
+    result
+}
+
+// only installs managed by dev-thing are removed, other version managers own theirs
+#[command]
+pub fn remove_node_version(version: String, state: State<'_, NodeManager>) -> Result<(), AppError> {
+    let target = state.install_dir.join(&version);
+    if Version::parse(&version).is_err() || !target.exists() {
+        return Err(AppError::new(ErrorCode::NotFound, format!("Node {} was not installed by dev-thing", version))
+            .with_hint("Remove it with the version manager that installed it"));
+    }
+    fs::remove_dir_all(&target).map_err(|e| AppError::io(e, &format!("remove node {}", version), &target))?;
+
+    let still_installed = state.discover().iter().any(|install| install.version == version);
+    if !still_installed {
+        let mut pins = state.pins.lock().unwrap();
+        pins.retain(|_, pinned| pinned != &version);
+        state.save_pins(&pins)?;
+    }
+    Ok(())
+}
+
+// passing no version goes back to whatever node is on PATH
+#[command]
+pub fn select_node_version(
+    project_path: String,
+    version: Option<String>,
+    state: State<'_, NodeManager>,
+) -> Result<(), AppError> {
+    if let Some(version) = &version {
+        if !state.discover().iter().any(|install| &install.version == version) {
+            return Err(AppError::new(ErrorCode::NotFound, format!("Node {} is not installed", version)));
+        }
+    }
+
+    let mut pins = state.pins.lock().unwrap();
+    match version {
+        Some(version) => pins.insert(project_path, version),
+        None => pins.remove(&project_path),
+    };
+    state.save_pins(&pins)
+}
+
+#[command]
+pub fn resolve_node_version(
+    project_path: String,
+    state: State<'_, NodeManager>,
+) -> Result<NodeResolution, AppError> {
+    state.resolve(&project_path)
+}
+
+#[command]
+pub fn get_project_node_version(
+    project_path: String,
+    state: State<'_, NodeManager>,
+) -> Result<Option<NodeInstall>, AppError> {
+    state.pinned_install(&project_path)
+}
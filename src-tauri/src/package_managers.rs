@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use serde_json::Value;
+use semver::Version;
+use tauri::{command, State};
+use crate::error::{read_package_json, AppError, ErrorCode};
+use crate::node_manager::NodeManager;
+use crate::utils::{resolve_executable, Argv};
+use crate::workspaces::find_workspace_root;
+
+const DENO_CONFIGS: [&str; 2] = ["deno.json", "deno.jsonc"];
+
+const MANAGERS: [&str; 5] = ["npm", "pnpm", "yarn", "bun", "deno"];
+
+// lockfiles and the manager writing them, in the order one is picked when
+// several are present and package.json doesn't say
+const LOCKFILES: [(&str, &str); 7] = [
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("bun.lock", "bun"),
+    ("bun.lockb", "bun"),
+    ("deno.lock", "deno"),
+    ("package-lock.json", "npm"),
+    ("npm-shrinkwrap.json", "npm"),
+];
+
+#[derive(Serialize)]
+pub struct PackageManagerInfo {
+    // npm, pnpm, yarn, bun or deno
+    pub name: String,
+    // exact version from package.json's packageManager, 9.1.0 for pnpm@9.1.0+sha512...
+    pub required_version: Option<String>,
+    // None when it isn't installed or wasn't checked
+    pub installed_version: Option<String>,
+    // what decided it: package.json, the lockfile's name, deno.json or default
+    pub source: String,
+    // lockfiles of the project, or of its workspace root for members
+    pub lockfiles: Vec<String>,
+    // the executable on PATH is a Corepack shim, which runs the required version
+    pub corepack: bool,
+    pub warnings: Vec<String>,
+}
+
+// deno.json / deno.jsonc, with whole line // comments of the latter skipped
+fn read_deno_config(path: &str) -> Option<Value> {
+    DENO_CONFIGS.iter().find_map(|file| {
+        let content = fs::read_to_string(Path::new(path).join(file)).ok()?;
+        let json: String = content.lines().filter(|line| !line.trim_start().starts_with("//")).collect::<Vec<_>>().join("\n");
+        serde_json::from_str(&json).ok()
+    })
+}
+
+// package.json with deno tasks added to its scripts; deno projects without
+// one get a stand-in built from deno.json's imports and tasks so framework
+// detection and scripts work the same for them
+pub fn project_manifest(path: &str) -> Result<Value, AppError> {
+    let deno_config = read_deno_config(path);
+    let mut manifest = match (read_package_json(path), &deno_config) {
+        (Ok(manifest), _) => manifest,
+        (Err(e), Some(config)) if e.code == ErrorCode::NotANodeProject => {
+            let imports: serde_json::Map<String, Value> = config["imports"]
+                .as_object()
+                .map(|imports| imports.iter().map(|(name, specifier)| (name.trim_end_matches('/').to_string(), specifier.clone())).collect())
+                .unwrap_or_default();
+            serde_json::json!({ "name": config["name"].clone(), "dependencies": imports })
+        }
+        (Err(e), _) => return Err(e),
+    };
+    if let (Some(tasks), Some(object)) = (deno_config.as_ref().and_then(|config| config["tasks"].as_object()), manifest.as_object_mut()) {
+        let scripts = object.entry("scripts").or_insert_with(|| Value::Object(Default::default()));
+        if let Some(scripts) = scripts.as_object_mut() {
+            for (name, task) in tasks {
+                // tasks can also be { "command": ..., "description": ... }
+                let command = task.as_str().map(str::to_string).or_else(|| task["command"].as_str().map(str::to_string)).unwrap_or_default();
+                scripts.entry(name.clone()).or_insert(Value::String(command));
+            }
+        }
+    }
+    Ok(manifest)
+}
+
+// the project's package manager from package.json's packageManager, then
+// lockfiles, then deno config; npm when nothing says otherwise. Workspace
+// members use the root's field and lockfile when they don't have their own
+pub fn detect(project_path: &str) -> PackageManagerInfo {
+    let project = Path::new(project_path);
+    let mut dirs = vec![project.to_path_buf()];
+    if let Some(root) = find_workspace_root(project_path).filter(|root| root != project) {
+        dirs.push(root);
+    }
+
+    let mut warnings = Vec::new();
+    let declared = dirs.iter().find_map(|dir| declared_package_manager(dir, &mut warnings));
+    let lockfiles: Vec<(PathBuf, &str)> = dirs
+        .iter()
+        .map(|dir| LOCKFILES.iter().map(|(file, manager)| (dir.join(file), *manager)).filter(|(path, _)| path.exists()).collect::<Vec<_>>())
+        .find(|found| !found.is_empty())
+        .unwrap_or_default();
+    let file_name = |path: &Path| path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+
+    let (name, required_version, source) = match declared {
+        Some((name, version)) => {
+            for (path, manager) in lockfiles.iter().filter(|(_, manager)| *manager != name) {
+                warnings.push(format!(
+                    "{} belongs to {} but package.json asks for {}; remove it so installs don't disagree",
+                    file_name(path),
+                    manager,
+                    name
+                ));
+            }
+            (name, Some(version), "package.json".to_string())
+        }
+        None => match lockfiles.first() {
+            Some((path, manager)) => {
+                let mut managers: Vec<&str> = lockfiles.iter().map(|(_, manager)| *manager).collect();
+                managers.dedup();
+                if managers.len() > 1 {
+                    warnings.push(format!(
+                        "Found lockfiles of {}, using {}; set packageManager in package.json or remove the others",
+                        managers.join(", "),
+                        manager
+                    ));
+                }
+                (manager.to_string(), None, file_name(path))
+            }
+            None => match DENO_CONFIGS.iter().find(|file| project.join(file).exists()) {
+                Some(file) => ("deno".to_string(), None, file.to_string()),
+                None => ("npm".to_string(), None, "default".to_string()),
+            },
+        },
+    };
+
+    PackageManagerInfo {
+        name,
+        required_version,
+        installed_version: None,
+        source,
+        lockfiles: lockfiles.iter().map(|(path, _)| path.to_string_lossy().to_string()).collect(),
+        corepack: false,
+        warnings,
+    }
+}
+
+// `pnpm@9.1.0+sha512.abc` as (pnpm, 9.1.0); unusable values are skipped
+// with a warning so detection falls back to the lockfiles
+fn declared_package_manager(dir: &Path, warnings: &mut Vec<String>) -> Option<(String, String)> {
+    let manifest: Value = serde_json::from_str(&fs::read_to_string(dir.join("package.json")).ok()?).ok()?;
+    let spec = manifest["packageManager"].as_str()?;
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, version.split('+').next().unwrap_or(version)),
+        None => {
+            warnings.push(format!("Ignoring packageManager \"{}\", it should look like pnpm@9.1.0", spec));
+            return None;
+        }
+    };
+    if !MANAGERS.contains(&name) {
+        warnings.push(format!("Ignoring packageManager \"{}\", {} isn't supported", spec, name));
+        return None;
+    }
+    // corepack refuses ranges and tags
+    if Version::parse(version).is_err() {
+        warnings.push(format!("packageManager \"{}\" should name an exact version like {}@9.1.0", spec, name));
+    }
+    Some((name.to_string(), version.to_string()))
+}
+
+// fills in the installed version and whether it's a Corepack shim, warning
+// when it's missing or doesn't match the required version
+pub fn check_installed(info: &mut PackageManagerInfo, project_path: &str, envs: &HashMap<String, String>) {
+    let program = match resolve_executable(&info.name, project_path, envs) {
+        Ok(program) => program,
+        Err(e) => {
+            info.warnings.push(e);
+            return;
+        }
+    };
+    info.corepack = is_corepack_shim(Path::new(&program));
+    info.installed_version = installed_version(&info.name, project_path, envs);
+
+    if let (Some(required), Some(installed)) = (&info.required_version, &info.installed_version) {
+        if required != installed {
+            let mut warning = format!("package.json asks for {} {} but {} is installed", info.name, required, installed);
+            if !info.corepack && resolve_executable("corepack", project_path, envs).is_ok() {
+                warning.push_str("; run `corepack enable` to use the required version");
+            }
+            info.warnings.push(warning);
+        }
+    }
+}
+
+// `<manager> --version`; deno prints `deno 1.46.3 (...)` and more lines
+fn installed_version(name: &str, project_path: &str, envs: &HashMap<String, String>) -> Option<String> {
+    let output = Argv::new(name).arg("--version").output(project_path, envs).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .next()?
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+// corepack links pnpm / yarn into its own folder, on windows it writes
+// small .cmd wrappers that call it
+fn is_corepack_shim(program: &Path) -> bool {
+    let resolved = fs::canonicalize(program).unwrap_or_else(|_| program.to_path_buf());
+    if resolved.components().any(|part| part.as_os_str().to_string_lossy().contains("corepack")) {
+        return true;
+    }
+    let small = fs::metadata(&resolved).map(|metadata| metadata.len() < 8192).unwrap_or(false);
+    small && fs::read_to_string(&resolved).map(|content| content.contains("corepack")).unwrap_or(false)
+}
+
+// the project's package manager with its installed version checked
+#[command]
+pub fn detect_runtime(project_path: String, node_state: State<'_, NodeManager>) -> Result<PackageManagerInfo, AppError> {
+    project_manifest(&project_path)?;
+    let mut info = detect(&project_path);
+    // corepack and the managers run on the project's node
+    let envs = node_state.command_env(&project_path)?;
+    check_installed(&mut info, &project_path, &envs);
+    Ok(info)
+}
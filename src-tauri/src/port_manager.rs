@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use serde::Serialize;
+use tauri::{command, State};
+use crate::project_manager::ProjectManager;
+use crate::error::{AppError, ErrorCode};
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+// where we start looking when a framework has no default port
+const FALLBACK_PORT: u16 = 3000;
+const LAST_PORT: u16 = 65535;
+
+// project path -> port, persisted to `file` so projects keep their port between sessions
+pub struct PortAllocator {
+    pub file: PathBuf,
+    pub assignments: Mutex<HashMap<String, u16>>,
+}
+
+#[derive(Serialize)]
+pub struct PortAssignment {
+    project: String,
+    port: u16,
+    in_use: bool,
+}
+
+#[derive(Serialize, Default)]
+pub struct PortOwner {
+    port: u16,
+    in_use: bool,
+    // project the port is assigned to in dev-thing, if any
+    assigned_project: Option<String>,
+    pid: Option<u32>,
+    command: Option<String>,
+    // whether the listening process is one dev-thing started
+    ours: bool,
+    // project / process name of our process listening on the port
+    project: Option<String>,
+    process: Option<String>,
+}
+
+impl PortAllocator {
+    pub fn load(data_dir: &Path) -> PortAllocator {
+        let file = data_dir.join("ports.json");
+        let assignments = fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        PortAllocator {
+            file,
+            assignments: Mutex::new(assignments),
+        }
+    }
+
+    fn save(&self, assignments: &HashMap<String, u16>) -> Result<(), AppError> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(e, "create data directory", parent))?;
+        }
+        let content = serde_json::to_string_pretty(assignments).map_err(|e| e.to_string())?;
+        fs::write(&self.file, content).map_err(|e| AppError::io(e, "save port assignments", &self.file))
+    }
+
+    // the project's own port if it is free, otherwise the first free port from
+    // `preferred` up that no other project has reserved
+    pub fn allocate(&self, project: &str, preferred: Option<u16>) -> Result<u16, AppError> {
+        let mut assignments = self.assignments.lock().unwrap();
+        if let Some(&port) = assignments.get(project) {
+            if is_port_free(port) {
+                return Ok(port);
+            }
+            println!("Port {} of {} is taken, picking another one", port, project);
+        }
+
+        let start = preferred.unwrap_or(FALLBACK_PORT);
+        let reserved: Vec<u16> = assignments
+            .iter()
+            .filter(|(other, _)| other.as_str() != project)
+            .map(|(_, &port)| port)
+            .collect();
+        let port = (start..=LAST_PORT)
+            .find(|port| !reserved.contains(port) && is_port_free(*port))
+            .ok_or_else(|| AppError::new(ErrorCode::PortUnavailable, format!("No free port available from {}", start)))?;
+
+        assignments.insert(project.to_string(), port);
+        self.save(&assignments)?;
+        Ok(port)
+    }
+
+    pub fn reserve(&self, project: &str, port: u16) -> Result<(), AppError> {
+        let mut assignments = self.assignments.lock().unwrap();
+        if let Some((other, _)) = assignments.iter().find(|(other, &assigned)| assigned == port && other.as_str() != project) {
+            return Err(AppError::new(ErrorCode::PortUnavailable, format!("Port {} is already reserved for {}", port, other))
+                .with_hint("Release it from the other project or pick another port"));
+        }
+        assignments.insert(project.to_string(), port);
+        self.save(&assignments)
+    }
+
+    pub fn release(&self, project: &str) -> Result<(), AppError> {
+        let mut assignments = self.assignments.lock().unwrap();
+        if assignments.remove(project).is_some() {
+            self.save(&assignments)?;
+        }
+        Ok(())
+    }
+
+    pub fn rename_project(&self, old: &str, new: &str) -> Result<(), AppError> {
+        let mut assignments = self.assignments.lock().unwrap();
+        if let Some(port) = assignments.remove(old) {
+            assignments.insert(new.to_string(), port);
+            self.save(&assignments)?;
+        }
+        Ok(())
+    }
+
+    pub fn project_for_port(&self, port: u16) -> Option<String> {
+        self.assignments
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, &assigned)| assigned == port)
+            .map(|(project, _)| project.clone())
+    }
+}
+
+// dev servers bind to all interfaces or only loopback depending on the
+// framework, so the port has to be free on every one of them
+pub fn is_port_free(port: u16) -> bool {
+    let ipv4_free = ["127.0.0.1", "0.0.0.0"].iter().all(|host| TcpListener::bind((*host, port)).is_ok());
+    // machines without IPv6 can't bind "::" at all
+    let ipv6_free = TcpListener::bind(("::", port)).is_ok() || TcpListener::bind(("::", 0)).is_err();
+    ipv4_free && ipv6_free
+}
+
+// pid and command of whatever is listening on the port
+#[cfg(unix)]
+fn listening_process(port: u16) -> Option<(u32, String)> {
+    let output = Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fpc"])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let pid = stdout.lines().find_map(|line| line.strip_prefix('p'))?.parse().ok()?;
+    let command = stdout.lines().find_map(|line| line.strip_prefix('c')).unwrap_or("").to_string();
+    Some((pid, command))
+}
+
+#[cfg(target_os = "windows")]
+fn listening_process(port: u16) -> Option<(u32, String)> {
+    let output = Command::new("netstat")
+        .args(["-ano", "-p", "TCP"])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // "  TCP    0.0.0.0:3000    0.0.0.0:0    LISTENING    1234"
+    let pid: u32 = stdout.lines().find_map(|line| {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        match columns.as_slice() {
+            [_, local, _, "LISTENING", pid] if local.ends_with(&format!(":{}", port)) => pid.parse().ok(),
+            _ => None,
+        }
+    })?;
+
+    let tasklist = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    let command = String::from_utf8_lossy(&tasklist.stdout)
+        .split(',')
+        .next()
+        .unwrap_or("")
+        .trim_matches(|c: char| c == '"' || c.is_whitespace())
+        .to_string();
+    Some((pid, command))
+}
+
+// our processes lead their own process group, the listener is usually a child
+#[cfg(unix)]
+fn process_group(pid: u32) -> Option<u32> {
+    nix::unistd::getpgid(Some(nix::unistd::Pid::from_raw(pid as i32)))
+        .ok()
+        .map(|pgid| pgid.as_raw() as u32)
+}
+
+#[cfg(target_os = "windows")]
+fn process_group(_pid: u32) -> Option<u32> {
+    None
+}
+
+#[command]
+pub fn list_port_assignments(state: State<'_, PortAllocator>) -> Result<Vec<PortAssignment>, AppError> {
+    let mut assignments: Vec<PortAssignment> = state
+        .assignments
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(project, &port)| PortAssignment {
+            project: project.clone(),
+            port,
+            in_use: !is_port_free(port),
+        })
+        .collect();
+    assignments.sort_by_key(|assignment| assignment.port);
+    Ok(assignments)
+}
+
+#[command]
+pub fn reserve_port(project_path: String, port: u16, state: State<'_, PortAllocator>) -> Result<(), AppError> {
+    state.reserve(&project_path, port)
+}
+
+#[command]
+pub fn release_port(project_path: String, state: State<'_, PortAllocator>) -> Result<(), AppError> {
+    state.release(&project_path)
+}
+
+#[command]
+pub fn get_port_owner(
+    port: u16,
+    state: State<'_, PortAllocator>,
+    project_state: State<'_, ProjectManager>,
+) -> Result<PortOwner, AppError> {
+    let mut owner = PortOwner {
+        port,
+        in_use: !is_port_free(port),
+        assigned_project: state.project_for_port(port),
+        ..Default::default()
+    };
+
+    if let Some((pid, command)) = listening_process(port) {
+        let ours = project_state
+            .find_by_pid(pid)
+            .or_else(|| process_group(pid).and_then(|pgid| project_state.find_by_pid(pgid)));
+        owner.pid = Some(pid);
+        owner.command = Some(command);
+        owner.ours = ours.is_some();
+        if let Some((project, process)) = ours {
+            owner.project = Some(project);
+            owner.process = Some(process);
+        }
+    }
+    Ok(owner)
+}
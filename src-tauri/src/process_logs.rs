@@ -0,0 +1,269 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, FixedOffset, Local};
+use regex::Regex;
+use serde::Serialize;
+use tauri::{command, State};
+use crate::utils::project_slug;
+use crate::error::{AppError, ErrorCode};
+
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+// rotated files kept next to the live one: dev.log.1 ... dev.log.5
+const MAX_ROTATED_FILES: usize = 5;
+const DEFAULT_TAIL_LINES: usize = 200;
+const DEFAULT_SEARCH_LIMIT: usize = 500;
+
+// logs live in `dir/<project slug>/<process name>.log`, each line written as
+// `[<rfc3339 timestamp>] [stdout|stderr] <text>`
+pub struct LogStore {
+    pub dir: PathBuf,
+}
+
+pub struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+pub type SharedLogFile = Arc<Mutex<LogFile>>;
+
+#[derive(Serialize)]
+pub struct LogMatch {
+    process: String,
+    line: String,
+}
+
+impl LogStore {
+    pub fn new(dir: PathBuf) -> LogStore {
+        LogStore { dir }
+    }
+
+    fn project_dir(&self, project: &str) -> PathBuf {
+        self.dir.join(project_slug(project))
+    }
+
+    pub fn open(&self, project: &str, name: &str) -> Result<SharedLogFile, AppError> {
+        let dir = self.project_dir(project);
+        fs::create_dir_all(&dir).map_err(|e| AppError::io(e, "create log directory", &dir))?;
+        let path = dir.join(format!("{}.log", file_safe(name)));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| AppError::io(e, "open log file", &path))?;
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+
+        Ok(Arc::new(Mutex::new(LogFile { path, file, size })))
+    }
+
+    // process name -> its log files, oldest first
+    fn process_logs(&self, project: &str, name: Option<&str>) -> Vec<(String, Vec<PathBuf>)> {
+        let dir = self.project_dir(project);
+        let mut names: Vec<String> = match name {
+            Some(name) => vec![file_safe(name)],
+            None => fs::read_dir(&dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .filter_map(|entry| {
+                            let file_name = entry.file_name().to_string_lossy().to_string();
+                            file_name.strip_suffix(".log").map(|name| name.to_string())
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        names.sort();
+
+        names
+            .into_iter()
+            .map(|name| {
+                let live = dir.join(format!("{}.log", name));
+                let mut files: Vec<PathBuf> = (1..=MAX_ROTATED_FILES)
+                    .rev()
+                    .map(|index| rotated_path(&live, index))
+                    .filter(|path| path.exists())
+                    .collect();
+                if live.exists() {
+                    files.push(live);
+                }
+                (name, files)
+            })
+            .collect()
+    }
+}
+
+impl LogFile {
+    pub fn write_line(&mut self, stream: &str, line: &str) {
+        let entry = format!("[{}] [{}] {}\n", Local::now().to_rfc3339(), stream, line);
+        if self.size + entry.len() as u64 > MAX_LOG_BYTES {
+            if let Err(e) = self.rotate() {
+                println!("Failed to rotate {}: {}", self.path.display(), e);
+            }
+        }
+        if self.file.write_all(entry.as_bytes()).is_ok() {
+            self.size += entry.len() as u64;
+        }
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let _ = fs::remove_file(rotated_path(&self.path, MAX_ROTATED_FILES));
+        for index in (1..MAX_ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(live: &Path, index: usize) -> PathBuf {
+    let mut path = live.as_os_str().to_owned();
+    path.push(format!(".{}", index));
+    PathBuf::from(path)
+}
+
+// process names come from package.json scripts, e.g. "db:migrate"
+fn file_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+fn line_timestamp(line: &str) -> Option<DateTime<FixedOffset>> {
+    let end = line.find(']')?;
+    DateTime::parse_from_rfc3339(line.get(1..end)?).ok()
+}
+
+fn parse_time(value: Option<String>, field: &str) -> Result<Option<DateTime<FixedOffset>>, AppError> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map_err(|e| AppError::new(ErrorCode::InvalidInput, format!("Invalid {} time: {}", field, e)).with_context(value.clone()))
+        })
+        .transpose()
+}
+
+fn read_lines(path: &Path) -> Vec<String> {
+    File::open(path)
+        .map(|file| BufReader::new(file).lines().map_while(Result::ok).collect())
+        .unwrap_or_default()
+}
+
+// last lines of one process, or of all the project's processes interleaved by time
+#[command]
+pub fn get_log_tail(
+    project_path: String,
+    name: Option<String>,
+    lines: Option<usize>,
+    state: State<'_, LogStore>,
+) -> Result<Vec<LogMatch>, AppError> {
+    let count = lines.unwrap_or(DEFAULT_TAIL_LINES);
+    let mut tail: Vec<LogMatch> = Vec::new();
+
+    for (process, files) in state.process_logs(&project_path, name.as_deref()) {
+        // newest file first until we have enough lines
+        let mut collected: Vec<String> = Vec::new();
+        for file in files.iter().rev() {
+            let mut lines = read_lines(file);
+            lines.append(&mut collected);
+            collected = lines;
+            if collected.len() >= count {
+                break;
+            }
+        }
+        let start = collected.len().saturating_sub(count);
+        tail.extend(collected.drain(start..).map(|line| LogMatch { process: process.clone(), line }));
+    }
+
+    tail.sort_by_key(|entry| line_timestamp(&entry.line));
+    let start = tail.len().saturating_sub(count);
+    Ok(tail.split_off(start))
+}
+
+#[command]
+pub fn search_logs(
+    project_path: String,
+    pattern: String,
+    name: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, LogStore>,
+) -> Result<Vec<LogMatch>, AppError> {
+    let regex = Regex::new(&pattern)
+        .map_err(|e| AppError::new(ErrorCode::InvalidInput, format!("Invalid search pattern: {}", e)).with_context(pattern.clone()))?;
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let mut matches = Vec::new();
+
+    for (process, files) in state.process_logs(&project_path, name.as_deref()) {
+        for file in files {
+            for line in read_lines(&file).into_iter().filter(|line| regex.is_match(line)) {
+                matches.push(LogMatch { process: process.clone(), line });
+            }
+        }
+    }
+
+    matches.sort_by_key(|entry| line_timestamp(&entry.line));
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+// writes a .tar.gz with one log per process, limited to lines between `from`
+// and `to` (RFC 3339, both optional), and returns the bundle path
+#[command]
+pub fn export_logs(
+    project_path: String,
+    destination: String,
+    from: Option<String>,
+    to: Option<String>,
+    state: State<'_, LogStore>,
+) -> Result<String, AppError> {
+    let from = parse_time(from, "start")?;
+    let to = parse_time(to, "end")?;
+    let in_range = |line: &String| match line_timestamp(line) {
+        Some(time) => from.map(|from| time >= from).unwrap_or(true) && to.map(|to| time <= to).unwrap_or(true),
+        None => from.is_none() && to.is_none(),
+    };
+
+    let mut bundle_path = PathBuf::from(&destination);
+    if bundle_path.is_dir() {
+        bundle_path = bundle_path.join(format!(
+            "{}-logs-{}.tar.gz",
+            project_slug(&project_path),
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+    }
+
+    let bundle = File::create(&bundle_path).map_err(|e| AppError::io(e, "create log bundle", &bundle_path))?;
+    let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(bundle, flate2::Compression::default()));
+
+    for (process, files) in state.process_logs(&project_path, None) {
+        let content: String = files
+            .iter()
+            .flat_map(|file| read_lines(file))
+            .filter(in_range)
+            .map(|line| line + "\n")
+            .collect();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(Local::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, format!("{}.log", process), content.as_bytes())
+            .map_err(|e| AppError::io(e, "write log bundle", &bundle_path))?;
+    }
+
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| AppError::io(e, "write log bundle", &bundle_path))?;
+
+    Ok(bundle_path.to_string_lossy().to_string())
+}
@@ -1,10 +1,29 @@
-use std::collections::HashMap;
-use std::process::Child;
-use std::sync::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tauri::{command, Manager, State};
+use crate::node_manager::NodeManager;
+use crate::process_logs::{LogStore, SharedLogFile};
+use crate::proxy::ProxyServer;
+use crate::env_manager::EnvManager;
+use crate::secrets::{mask_secrets, SecretStore};
+use crate::log_parser::parse_line;
+use crate::utils::{parse_command, resolve_executable};
+use crate::error::{AppError, ErrorCode};
 #[cfg(unix)]
 use nix::unistd::Pid;
 #[cfg(unix)]
-use nix::sys::signal::{kill, Signal};
+use nix::sys::signal::{killpg, Signal};
+#[cfg(unix)]
+use nix::errno::Errno;
 #[cfg(target_os = "windows")]
 use winapi::um::processthreadsapi::{OpenProcess, TerminateProcess};
 #[cfg(target_os = "windows")]
@@ -14,29 +33,954 @@ use winapi::um::handleapi::CloseHandle;
 #[cfg(target_os = "windows")]
 use winapi::shared::minwindef::DWORD;
 
-pub struct ProjectManager(pub Mutex<HashMap<u32, Child>>);
+// lines of output kept per process for exit reports
+const LAST_LINES: usize = 50;
+// exit reports kept for close_project / the UI after the process is gone
+const RECENT_EXITS: usize = 50;
+// a process that stayed up this long is considered healthy again and its
+// restart backoff starts over
+const STABLE_UPTIME: Duration = Duration::from_secs(30);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+// everything needed to (re)spawn a process
+#[derive(Clone, Debug)]
+pub struct ProcessSpec {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: String,
+    pub envs: HashMap<String, String>,
+    // port assigned to the process, if it is a server
+    pub port: Option<u16>,
+    // secret values in `envs`, masked in output and logs
+    pub masked: Vec<String>,
+}
+
+impl ProcessSpec {
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(|arg| arg.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessStatus {
+    Running,
+    Stopping,
+}
+
+// set by the supervisor once the child has been reaped
+#[derive(Default)]
+pub struct ExitWatch {
+    exited: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl ExitWatch {
+    fn notify(&self) {
+        *self.exited.lock().unwrap() = true;
+        self.condvar.notify_all();
+    }
+
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let exited = self.exited.lock().unwrap();
+        let (exited, _) = self.condvar.wait_timeout_while(exited, timeout, |exited| !*exited).unwrap();
+        *exited
+    }
+}
+
+pub struct ManagedProcess {
+    pub pid: u32,
+    pub spec: ProcessSpec,
+    pub started_at: DateTime<Local>,
+    pub status: ProcessStatus,
+    pub exit: Arc<ExitWatch>,
+    // first local server url the process printed
+    pub url: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ProcessInfo {
+    name: String,
+    pid: u32,
+    command: String,
+    started_at: String,
+    status: ProcessStatus,
+    port: Option<u16>,
+    url: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitReason {
+    // stopped through dev-thing
+    Stopped,
+    // exited on its own with code 0
+    Exited,
+    // non-zero exit code or killed by a signal nobody in dev-thing sent
+    Crashed,
+}
+
+// payload of the `project-exited` event
+#[derive(Serialize, Clone, Debug)]
+pub struct ProcessExit {
+    pub project: String,
+    pub name: String,
+    pub pid: u32,
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    pub reason: ExitReason,
+    pub exited_at: String,
+    pub uptime_ms: Option<u64>,
+    pub last_lines: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure { max_retries: u32 },
+    Always,
+}
+
+// restart bookkeeping for a named process, outlives the process itself
+#[derive(Default)]
+pub struct Supervision {
+    pub policy: RestartPolicy,
+    pub restart_count: u32,
+    // failures in a row without reaching STABLE_UPTIME, drives the backoff
+    pub consecutive_failures: u32,
+    pub last_failure: Option<String>,
+    pub next_restart_at: Option<DateTime<Local>>,
+    // what to start once the backoff elapsed, taken away when cancelled
+    pending_restart: Option<ProcessSpec>,
+}
+
+#[derive(Serialize)]
+pub struct RestartStatus {
+    name: String,
+    policy: RestartPolicy,
+    restart_count: u32,
+    last_failure: Option<String>,
+    next_restart_at: Option<String>,
+}
+
+// payload of the `project-restarting` event
+#[derive(Serialize, Clone)]
+pub struct RestartScheduled {
+    project: String,
+    name: String,
+    attempt: u32,
+    delay_ms: u64,
+}
+
+// what the pid file remembers about a process, so a later session can find
+// processes left behind by a crash or a forced quit
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PersistedProcess {
+    pub pid: u32,
+    pub project: String,
+    pub name: String,
+    pub command: String,
+    pub started_at: String,
+}
+
+#[derive(Default)]
+pub struct ProjectManager {
+    // project path -> process name (dev, a script name, ...) -> process
+    pub processes: Mutex<HashMap<String, HashMap<String, ManagedProcess>>>,
+    pub recent_exits: Mutex<VecDeque<ProcessExit>>,
+    // project path -> process name -> restart policy and counters
+    pub supervision: Mutex<HashMap<String, HashMap<String, Supervision>>>,
+    // processes from a previous session that are still running
+    pub orphans: Mutex<Vec<PersistedProcess>>,
+    pub pid_file: Option<PathBuf>,
+    shutting_down: AtomicBool,
+}
+
+impl ProjectManager {
+    // picks up the previous session's pid file and keeps whatever is still alive as orphans
+    pub fn load(data_dir: &Path) -> ProjectManager {
+        let pid_file = data_dir.join("processes.json");
+        let previous: Vec<PersistedProcess> = fs::read_to_string(&pid_file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let orphans: Vec<PersistedProcess> = previous.into_iter().filter(is_still_running).collect();
+        if !orphans.is_empty() {
+            println!("Found {} process(es) left over from the last session", orphans.len());
+        }
+
+        let manager = ProjectManager {
+            orphans: Mutex::new(orphans),
+            pid_file: Some(pid_file),
+            ..Default::default()
+        };
+        manager.save_pid_file();
+        manager
+    }
+
+    fn save_pid_file(&self) {
+        let pid_file = match &self.pid_file {
+            Some(pid_file) => pid_file,
+            None => return,
+        };
+
+        let mut persisted: Vec<PersistedProcess> = {
+            let projects = self.processes.lock().unwrap();
+            projects
+                .iter()
+                .flat_map(|(project, processes)| {
+                    processes.iter().map(move |(name, process)| PersistedProcess {
+                        pid: process.pid,
+                        project: project.clone(),
+                        name: name.clone(),
+                        command: process.spec.program.clone(),
+                        started_at: process.started_at.to_rfc3339(),
+                    })
+                })
+                .collect()
+        };
+        // orphans nobody dealt with yet are still worth reporting next time
+        persisted.extend(self.orphans.lock().unwrap().iter().cloned());
+
+        let result = pid_file
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| fs::write(pid_file, serde_json::to_string_pretty(&persisted).unwrap_or_default()));
+        if let Err(e) = result {
+            println!("Failed to write pid file: {}", e);
+        }
+    }
+
+    // used when the app quits: stops every tracked process tree, waits for all
+    // of them within `grace_period` and kills what is left
+    pub fn stop_all(&self, grace_period: Duration) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        for processes in self.supervision.lock().unwrap().values_mut() {
+            for supervision in processes.values_mut() {
+                supervision.pending_restart = None;
+                supervision.next_restart_at = None;
+            }
+        }
+
+        let running: Vec<(u32, Arc<ExitWatch>)> = {
+            let mut projects = self.processes.lock().unwrap();
+            projects
+                .values_mut()
+                .flat_map(|processes| processes.values_mut())
+                .map(|process| {
+                    process.status = ProcessStatus::Stopping;
+                    (process.pid, process.exit.clone())
+                })
+                .collect()
+        };
+        if running.is_empty() {
+            return;
+        }
+        println!("Stopping {} process(es) before exit", running.len());
+
+        for (pid, _) in &running {
+            let _ = signal_tree(*pid, false);
+        }
+        let deadline = Instant::now() + grace_period;
+        for (pid, exit) in &running {
+            let exited = exit.wait_timeout(deadline.saturating_duration_since(Instant::now()));
+            if !exited || tree_alive(*pid) {
+                let _ = signal_tree(*pid, true);
+            }
+        }
+        for (_, exit) in &running {
+            exit.wait_timeout(Duration::from_secs(1));
+        }
+        self.save_pid_file();
+    }
+
+    // (project, name) of the process with this pid
+    pub fn find_by_pid(&self, pid: u32) -> Option<(String, String)> {
+        let projects = self.processes.lock().unwrap();
+        projects.iter().find_map(|(project, processes)| {
+            processes
+                .iter()
+                .find(|(_, process)| process.pid == pid)
+                .map(|(name, _)| (project.clone(), name.clone()))
+        })
+    }
+
+    pub fn is_running(&self, project: &str, name: &str) -> bool {
+        self.processes
+            .lock()
+            .unwrap()
+            .get(project)
+            .map(|processes| processes.contains_key(name))
+            .unwrap_or(false)
+    }
+
+    fn set_url(&self, project: &str, name: &str, pid: u32, url: String) {
+        let mut projects = self.processes.lock().unwrap();
+        let process = projects.get_mut(project).and_then(|processes| processes.get_mut(name));
+        if let Some(process) = process.filter(|process| process.pid == pid) {
+            process.url = Some(url);
+        }
+    }
+
+    pub fn recent_exit(&self, pid: u32) -> Option<ProcessExit> {
+        self.recent_exits.lock().unwrap().iter().rev().find(|exit| exit.pid == pid).cloned()
+    }
+
+    // returns the spec so the process can be started again; a process waiting
+    // for its restart backoff is stopped by cancelling the restart
+    pub fn stop(&self, project: &str, name: &str, grace_period: Duration) -> Result<ProcessSpec, AppError> {
+        let running = {
+            let mut projects = self.processes.lock().unwrap();
+            projects
+                .get_mut(project)
+                .and_then(|processes| processes.get_mut(name))
+                .map(|process| {
+                    process.status = ProcessStatus::Stopping;
+                    (process.pid, process.spec.clone(), process.exit.clone())
+                })
+        };
+
+        match running {
+            Some((pid, spec, exit)) => {
+                terminate_process_tree(pid, &exit, grace_period)?;
+                Ok(spec)
+            }
+            None => self
+                .with_supervision(project, name, |supervision| {
+                    supervision.next_restart_at = None;
+                    supervision.pending_restart.take()
+                })
+                .ok_or_else(|| AppError::new(ErrorCode::NotFound, format!("No process named \"{}\" for this project", name))),
+        }
+    }
+
+    fn with_supervision<T>(&self, project: &str, name: &str, f: impl FnOnce(&mut Supervision) -> T) -> T {
+        let mut supervision = self.supervision.lock().unwrap();
+        let entry = supervision
+            .entry(project.to_string())
+            .or_default()
+            .entry(name.to_string())
+            .or_default();
+        f(entry)
+    }
+
+    pub fn set_restart_policy(&self, project: &str, name: &str, policy: RestartPolicy) {
+        self.with_supervision(project, name, |supervision| {
+            supervision.policy = policy;
+            supervision.consecutive_failures = 0;
+        });
+    }
+
+    // decides whether the exited process comes back and after how long
+    fn plan_restart(&self, exit: &ProcessExit, spec: &ProcessSpec) -> Option<Duration> {
+        if exit.reason == ExitReason::Stopped || self.shutting_down.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        self.with_supervision(&exit.project, &exit.name, |supervision| {
+            if exit.uptime_ms.map(|uptime| uptime >= STABLE_UPTIME.as_millis() as u64).unwrap_or(false) {
+                supervision.consecutive_failures = 0;
+            }
+            let restart = match supervision.policy {
+                RestartPolicy::Never => false,
+                RestartPolicy::Always => true,
+                RestartPolicy::OnFailure { max_retries } => {
+                    exit.reason == ExitReason::Crashed && supervision.consecutive_failures < max_retries
+                }
+            };
+            if exit.reason == ExitReason::Crashed {
+                supervision.last_failure = Some(describe_failure(exit));
+            }
+            if !restart {
+                return None;
+            }
+
+            supervision.consecutive_failures += 1;
+            supervision.restart_count += 1;
+            let delay = restart_delay(supervision.consecutive_failures);
+            supervision.next_restart_at = chrono::Duration::from_std(delay).ok().map(|delay| Local::now() + delay);
+            supervision.pending_restart = Some(spec.clone());
+            Some(delay)
+        })
+    }
+
+    fn take_pending_restart(&self, project: &str, name: &str) -> Option<ProcessSpec> {
+        self.with_supervision(project, name, |supervision| {
+            supervision.next_restart_at = None;
+            supervision.pending_restart.take()
+        })
+    }
+
+    // called by the supervisor once the child is reaped
+    fn record_exit(&self, project: &str, name: &str, pid: u32, status: Option<ExitStatus>, last_lines: Vec<String>) -> ProcessExit {
+        let (stopped_by_user, started_at) = {
+            let mut projects = self.processes.lock().unwrap();
+            let processes = projects.get_mut(project);
+            let tracked = processes
+                .as_ref()
+                .and_then(|processes| processes.get(name))
+                .filter(|process| process.pid == pid)
+                .map(|process| (process.status == ProcessStatus::Stopping, process.started_at));
+            if let (Some(processes), Some(_)) = (processes, tracked) {
+                processes.remove(name);
+                if processes.is_empty() {
+                    projects.remove(project);
+                }
+            }
+            (tracked.map(|(stopping, _)| stopping).unwrap_or(false), tracked.map(|(_, started_at)| started_at))
+        };
+
+        let code = status.and_then(|status| status.code());
+        let signal = status.and_then(exit_signal);
+        let reason = if stopped_by_user {
+            ExitReason::Stopped
+        } else if code == Some(0) {
+            ExitReason::Exited
+        } else {
+            ExitReason::Crashed
+        };
+
+        let exit = ProcessExit {
+            project: project.to_string(),
+            name: name.to_string(),
+            pid,
+            code,
+            signal,
+            reason,
+            exited_at: Local::now().to_rfc3339(),
+            uptime_ms: started_at.map(|started_at| (Local::now() - started_at).num_milliseconds().max(0) as u64),
+            last_lines,
+        };
+
+        self.save_pid_file();
+        let mut recent_exits = self.recent_exits.lock().unwrap();
+        if recent_exits.len() == RECENT_EXITS {
+            recent_exits.pop_front();
+        }
+        recent_exits.push_back(exit.clone());
+        exit
+    }
+}
+
+fn describe_failure(exit: &ProcessExit) -> String {
+    match (exit.code, exit.signal) {
+        (_, Some(signal)) => format!("killed by signal {}", signal),
+        (Some(code), _) => format!("exited with code {}", code),
+        (None, None) => "exited with an unknown status".to_string(),
+    }
+}
+
+// 1s, 2s, 4s, ... capped at MAX_RESTART_DELAY
+fn restart_delay(attempt: u32) -> Duration {
+    let delay = Duration::from_secs(1).saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+    delay.min(MAX_RESTART_DELAY)
+}
+
+#[cfg(unix)]
+fn exit_signal(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(target_os = "windows")]
+fn exit_signal(_status: ExitStatus) -> Option<i32> {
+    None
+}
+
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+pub fn grace_period_from_ms(grace_period_ms: Option<u64>) -> Duration {
+    grace_period_ms.map(Duration::from_millis).unwrap_or(DEFAULT_GRACE_PERIOD)
+}
+
+struct StreamTarget {
+    project: String,
+    name: String,
+    pid: u32,
+    window: tauri::Window,
+    last_lines: Arc<Mutex<VecDeque<String>>>,
+    log_file: Option<SharedLogFile>,
+    masked: Vec<String>,
+}
+
+// parses every line into a `LogRecord`, writes it to the log file, keeps it
+// for exit reports and emits it as `event`
+fn stream_lines<R: Read + Send + 'static>(reader: R, event: &'static str, target: StreamTarget) {
+    let stream = if event == "project-error" { "stderr" } else { "stdout" };
+    thread::spawn(move || {
+        let reader = BufReader::new(reader);
+        let mut url_found = false;
+        for line in reader.lines().map_while(Result::ok) {
+            let line = mask_secrets(&line, &target.masked);
+            let record = parse_line(target.pid, &target.project, &target.name, stream, &line);
+            if let Some(log_file) = &target.log_file {
+                log_file.lock().unwrap().write_line(stream, &record.text);
+            }
+            {
+                let mut last_lines = target.last_lines.lock().unwrap();
+                if last_lines.len() == LAST_LINES {
+                    last_lines.pop_front();
+                }
+                last_lines.push_back(record.text.clone());
+            }
+            if let (false, Some(port)) = (url_found, record.port) {
+                url_found = true;
+                // "listening on port 3000" style lines don't come with a url
+                let url = record
+                    .urls
+                    .iter()
+                    .find(|url| url.contains(&format!(":{}", port)))
+                    .cloned()
+                    .unwrap_or_else(|| format!("http://localhost:{}", port));
+                target.window.state::<ProjectManager>().set_url(&target.project, &target.name, target.pid, url);
+            }
+            target.window.emit(event, record).unwrap();
+        }
+    });
+}
+
+// spawns the process under `name` for the project with piped output, streams
+// every line to the window as `project-output` / `project-error` events and
+// supervises it: once it exits a `project-exited` event is emitted and the
+// entry is dropped from the manager
+pub fn spawn_tracked(
+    project: &str,
+    name: &str,
+    spec: ProcessSpec,
+    window: tauri::Window,
+    state: &ProjectManager,
+) -> Result<u32, AppError> {
+    if state.shutting_down.load(Ordering::SeqCst) {
+        return Err(AppError::new(ErrorCode::ShuttingDown, "dev-thing is shutting down"));
+    }
+    let mut projects = state.processes.lock().unwrap();
+    let processes = projects.entry(project.to_string()).or_default();
+    if let Some(existing) = processes.get(name) {
+        return Err(AppError::new(ErrorCode::AlreadyRunning, format!("\"{}\" is already running (pid {})", name, existing.pid)));
+    }
+
+    let mut command = Command::new(&spec.program);
+    command.args(&spec.args)
+        .envs(&spec.envs)
+        .current_dir(&spec.cwd)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // own process group / session, so the whole tree can be signalled at once
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        unsafe {
+            command.pre_exec(|| nix::unistd::setsid().map(|_| ()).map_err(std::io::Error::from));
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000 | 0x00000200); // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| AppError::io(e, &format!("start {}", spec.program), Path::new(&spec.cwd)))?;
+    let pid = child.id();
+
+    // Stream output
+    let stdout = child.stdout.take().ok_or_else(|| AppError::new(ErrorCode::Process, "Failed to capture stdout"))?;
+    let stderr = child.stderr.take().ok_or_else(|| AppError::new(ErrorCode::Process, "Failed to capture stderr"))?;
+    let last_lines = Arc::new(Mutex::new(VecDeque::with_capacity(LAST_LINES)));
+    let log_file = window
+        .state::<LogStore>()
+        .open(project, name)
+        .map_err(|e| println!("Not writing logs for {}: {}", name, e))
+        .ok();
+    let target = |log_file| StreamTarget {
+        project: project.to_string(),
+        name: name.to_string(),
+        pid,
+        window: window.clone(),
+        last_lines: last_lines.clone(),
+        log_file,
+        masked: spec.masked.clone(),
+    };
+    stream_lines(stdout, "project-output", target(log_file.clone()));
+    stream_lines(stderr, "project-error", target(log_file));
+
+    let exit = Arc::new(ExitWatch::default());
+    processes.insert(name.to_string(), ManagedProcess {
+        pid,
+        spec: spec.clone(),
+        started_at: Local::now(),
+        status: ProcessStatus::Running,
+        exit: exit.clone(),
+        url: None,
+    });
+
+    drop(projects);
+    state.save_pid_file();
+    if let Some(port) = spec.port {
+        window.state::<ProxyServer>().add_route(project, port);
+    }
+    // a manual start supersedes a scheduled restart
+    state.take_pending_restart(project, name);
+
+    // Supervise the child
+    let project = project.to_string();
+    let name = name.to_string();
+    thread::spawn(move || {
+        let status = child.wait().ok();
+        // give the readers a moment to drain what the process wrote last
+        thread::sleep(Duration::from_millis(200));
+        let last_lines: Vec<String> = last_lines.lock().unwrap().iter().cloned().collect();
+
+        let state = window.state::<ProjectManager>();
+        let report = state.record_exit(&project, &name, pid, status, last_lines);
+        // before notifying, restart / stop return on it and the next server
+        // adds its own route right away
+        if let Some(port) = spec.port {
+            window.state::<ProxyServer>().remove_route(&project, port);
+        }
+        exit.notify();
+
+        println!("Process {} ({}) exited: {:?}", pid, name, report.reason);
+        window.emit("project-exited", report.clone()).unwrap();
+
+        if let Some(delay) = state.plan_restart(&report, &spec) {
+            let attempt = state.with_supervision(&project, &name, |supervision| supervision.consecutive_failures);
+            println!("Restarting {} in {:?} (attempt {})", name, delay, attempt);
+            window.emit("project-restarting", RestartScheduled {
+                project: project.clone(),
+                name: name.clone(),
+                attempt,
+                delay_ms: delay.as_millis() as u64,
+            }).unwrap();
+
+            thread::sleep(delay);
+            if let Some(spec) = state.take_pending_restart(&project, &name) {
+                if let Err(e) = spawn_tracked(&project, &name, spec, window.clone(), &state) {
+                    println!("Failed to restart {}: {}", name, e);
+                    state.with_supervision(&project, &name, |supervision| supervision.last_failure = Some(e.message));
+                }
+            }
+        }
+    });
+
+    Ok(pid)
+}
+
+#[command]
+pub fn list_processes(project_path: String, state: State<'_, ProjectManager>) -> Result<Vec<ProcessInfo>, AppError> {
+    let projects = state.processes.lock().unwrap();
+    let mut processes: Vec<ProcessInfo> = projects
+        .get(&project_path)
+        .map(|processes| {
+            processes
+                .iter()
+                .map(|(name, process)| ProcessInfo {
+                    name: name.clone(),
+                    pid: process.pid,
+                    command: process.spec.command_line(),
+                    started_at: process.started_at.to_rfc3339(),
+                    status: process.status,
+                    port: process.spec.port,
+                    url: process.url.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    processes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(processes)
+}
+
+// starts an arbitrary command line (no shell) as a named process of the project,
+// e.g. name "typecheck" with command "tsc --noEmit --watch"
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub fn start_process(
+    project_path: String,
+    name: String,
+    command: String,
+    restart_policy: Option<RestartPolicy>,
+    window: tauri::Window,
+    state: State<'_, ProjectManager>,
+    node_state: State<'_, NodeManager>,
+    env_state: State<'_, EnvManager>,
+    secret_state: State<'_, SecretStore>,
+) -> Result<u32, AppError> {
+    let (program, args) = parse_command(&command).map_err(|e| AppError::new(ErrorCode::InvalidInput, e).with_context(command.clone()))?;
+    let mut envs = env_state.project_env(&project_path)?;
+    let masked = secret_state.inject(&project_path, &mut envs)?;
+    envs.extend(node_state.command_env(&project_path)?);
+    let program = resolve_executable(&program, &project_path, &envs).map_err(|e| {
+        AppError::new(ErrorCode::NotFound, e).with_hint("Install the project's dependencies or check the command for typos")
+    })?;
+
+    let spec = ProcessSpec {
+        program,
+        args,
+        cwd: project_path.clone(),
+        envs,
+        port: None,
+        masked,
+    };
+    if let Some(policy) = restart_policy {
+        state.set_restart_policy(&project_path, &name, policy);
+    }
+    spawn_tracked(&project_path, &name, spec, window, &state)
+}
+
+#[command]
+pub fn set_restart_policy(
+    project_path: String,
+    name: String,
+    policy: RestartPolicy,
+    state: State<'_, ProjectManager>,
+) -> Result<(), AppError> {
+    state.set_restart_policy(&project_path, &name, policy);
+    Ok(())
+}
+
+#[command]
+pub fn get_restart_status(project_path: String, state: State<'_, ProjectManager>) -> Result<Vec<RestartStatus>, AppError> {
+    let supervision = state.supervision.lock().unwrap();
+    let mut statuses: Vec<RestartStatus> = supervision
+        .get(&project_path)
+        .map(|processes| {
+            processes
+                .iter()
+                .map(|(name, supervision)| RestartStatus {
+                    name: name.clone(),
+                    policy: supervision.policy,
+                    restart_count: supervision.restart_count,
+                    last_failure: supervision.last_failure.clone(),
+                    next_restart_at: supervision.next_restart_at.map(|at| at.to_rfc3339()),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    statuses.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(statuses)
+}
+
+#[command]
+pub fn stop_process(
+    project_path: String,
+    name: String,
+    grace_period_ms: Option<u64>,
+    state: State<'_, ProjectManager>,
+) -> Result<(), AppError> {
+    state.stop(&project_path, &name, grace_period_from_ms(grace_period_ms)).map(|_| ())
+}
+
+#[command]
+pub fn restart_process(
+    project_path: String,
+    name: String,
+    grace_period_ms: Option<u64>,
+    window: tauri::Window,
+    state: State<'_, ProjectManager>,
+) -> Result<u32, AppError> {
+    let spec = state.stop(&project_path, &name, grace_period_from_ms(grace_period_ms))?;
+    spawn_tracked(&project_path, &name, spec, window, &state)
+}
+
+#[command]
+pub fn list_orphan_processes(state: State<'_, ProjectManager>) -> Result<Vec<PersistedProcess>, AppError> {
+    let mut orphans = state.orphans.lock().unwrap();
+    orphans.retain(is_still_running);
+    Ok(orphans.clone())
+}
+
+// kills the given orphans (all of them when no pids are passed) and forgets
+// them; passing `kill: false` just forgets them and leaves them running.
+// Orphans that couldn't be signalled are kept so they can be retried
+#[command]
+pub fn handle_orphan_processes(
+    pids: Option<Vec<u32>>,
+    kill: bool,
+    grace_period_ms: Option<u64>,
+    state: State<'_, ProjectManager>,
+) -> Result<(), AppError> {
+    let selected: Vec<PersistedProcess> = {
+        let mut orphans = state.orphans.lock().unwrap();
+        let (selected, kept) = orphans
+            .drain(..)
+            .partition(|orphan| pids.as_ref().map(|pids| pids.contains(&orphan.pid)).unwrap_or(true));
+        *orphans = kept;
+        selected
+    };
+
+    let mut errors: Vec<(u32, AppError)> = Vec::new();
+    if kill {
+        let grace_period = grace_period_from_ms(grace_period_ms);
+        for orphan in selected.iter().filter(|orphan| is_still_running(orphan)) {
+            println!("Killing orphaned process {} ({})", orphan.pid, orphan.command);
+            if let Err(e) = signal_tree(orphan.pid, false) {
+                errors.push((orphan.pid, e));
+            }
+        }
+        let failed = |orphan: &PersistedProcess, errors: &[(u32, AppError)]| errors.iter().any(|(pid, _)| *pid == orphan.pid);
+        let deadline = Instant::now() + grace_period;
+        while Instant::now() < deadline && selected.iter().any(|orphan| !failed(orphan, &errors) && is_still_running(orphan)) {
+            thread::sleep(Duration::from_millis(100));
+        }
+        let remaining: Vec<u32> = selected
+            .iter()
+            .filter(|orphan| !failed(orphan, &errors) && is_still_running(orphan))
+            .map(|orphan| orphan.pid)
+            .collect();
+        for pid in remaining {
+            if let Err(e) = signal_tree(pid, true) {
+                errors.push((pid, e));
+            }
+        }
+        let kept = selected.into_iter().filter(|orphan| failed(orphan, &errors));
+        state.orphans.lock().unwrap().extend(kept);
+    }
+
+    state.save_pid_file();
+    match errors.first() {
+        None => Ok(()),
+        Some((_, first)) => {
+            let failures: Vec<String> = errors.iter().map(|(pid, e)| format!("{}: {}", pid, e)).collect();
+            let mut error = AppError::new(first.code, format!("Could not stop orphaned processes ({})", failures.join("; ")));
+            error.hint = first.hint.clone();
+            Err(error)
+        }
+    }
+}
+
+// the pid could have been reused since, so the command has to match as well
+fn is_still_running(process: &PersistedProcess) -> bool {
+    let program = Path::new(&process.command)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    process_command_line(process.pid)
+        .map(|command_line| command_line.to_lowercase().contains(&program))
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn process_command_line(pid: u32) -> Option<String> {
+    let output = Command::new("ps").args(["-o", "command=", "-p", &pid.to_string()]).output().ok()?;
+    let command_line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !command_line.is_empty() {
+        Some(command_line)
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn process_command_line(pid: u32) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    // "node.exe","1234",... or an INFO line when nothing matches
+    let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if line.starts_with('"') {
+        Some(line)
+    } else {
+        None
+    }
+}
+
+// asks the whole tree to stop (SIGTERM to the process group on unix) and kills
+// whatever is left after `grace_period`. The supervisor reaps the child, so this
+// only returns once the top process is really gone.
+pub fn terminate_process_tree(pid: u32, exit: &ExitWatch, grace_period: Duration) -> Result<(), AppError> {
+    signal_tree(pid, false)?;
+
+    let deadline = Instant::now() + grace_period;
+    while Instant::now() < deadline {
+        if exit.wait_timeout(Duration::from_millis(100)) && !tree_alive(pid) {
+            return Ok(());
+        }
+    }
+
+    println!("Process tree {} did not stop within {:?}, killing it", pid, grace_period);
+    signal_tree(pid, true)?;
+    if !exit.wait_timeout(DEFAULT_GRACE_PERIOD) {
+        return Err(AppError::new(ErrorCode::Process, format!("Process {} did not exit after being killed", pid)));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn signal_tree(pid: u32, force: bool) -> Result<(), AppError> {
+    let signal = if force { Signal::SIGKILL } else { Signal::SIGTERM };
+    match killpg(Pid::from_raw(pid as i32), signal) {
+        Ok(()) | Err(Errno::ESRCH) => Ok(()),
+        Err(Errno::EPERM) => Err(AppError::new(ErrorCode::PermissionDenied, format!("Not allowed to stop process {}", pid))
+            .with_hint("It was started by another user, stop it from there")),
+        Err(e) => Err(AppError::new(ErrorCode::Process, format!("Failed to signal process {}: {}", pid, e))),
+    }
+}
+
+// the leader is reaped by then, so any hit is a leftover child
+#[cfg(unix)]
+fn tree_alive(pid: u32) -> bool {
+    killpg(Pid::from_raw(pid as i32), None).is_ok()
+}
 
 #[cfg(target_os = "windows")]
-pub fn terminate_process(pid: u32) -> Result<(), String> {
+fn signal_tree(pid: u32, force: bool) -> Result<(), AppError> {
+    use std::os::windows::process::CommandExt;
+
+    let pid_arg = pid.to_string();
+    let mut args = vec!["/PID", pid_arg.as_str(), "/T"];
+    if force {
+        args.push("/F");
+    }
+    let status = Command::new("taskkill")
+        .args(&args)
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .status();
+
+    match status {
+        Ok(_) if !force => Ok(()),
+        Ok(status) if status.success() => Ok(()),
+        // taskkill missing or refused, at least take down the top process
+        _ => terminate_process(pid),
+    }
+}
+
+// console processes can't be asked to close on windows, so only the
+// top process is waited for before `taskkill /F` takes the tree down
+#[cfg(target_os = "windows")]
+fn tree_alive(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(target_os = "windows")]
+fn terminate_process(pid: u32) -> Result<(), AppError> {
     unsafe {
         let process_handle: HANDLE = OpenProcess(PROCESS_TERMINATE, 0, pid as DWORD);
         if process_handle.is_null() {
             println!("Failed to open process");
-            return Err("Failed to open process".to_string());
+            return Err(AppError::new(ErrorCode::PermissionDenied, format!("Failed to open process {}", pid)));
         }
 
         if TerminateProcess(process_handle, 1) == 0 {
             CloseHandle(process_handle);
             println!("Failed to terminate process");
-            return Err("Failed to terminate process".to_string());
+            return Err(AppError::new(ErrorCode::Process, format!("Failed to terminate process {}", pid)));
         }
 
         CloseHandle(process_handle);
     }
     Ok(())
 }
-
-#[cfg(unix)]
-pub fn terminate_process(pid: u32) -> Result<(), String> {
-    kill(Pid::from_raw(pid as i32), Signal::SIGTERM).map_err(|e| e.to_string())
-}
\ No newline at end of file
@@ -0,0 +1,377 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use crate::commands::{analyze, ProjectInfo};
+use crate::package_managers;
+use crate::frameworks;
+use crate::error::{read_package_json, AppError, ErrorCode};
+use crate::env_manager::EnvManager;
+use crate::node_manager::NodeManager;
+use crate::port_manager::PortAllocator;
+use crate::secrets::SecretStore;
+use crate::utils::project_slug;
+
+const DEFAULT_SCAN_DEPTH: usize = 4;
+const MAX_SCAN_DEPTH: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RegisteredProject {
+    // stays the same when the project is renamed or moved
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub framework: Option<String>,
+    pub package_manager: Option<String>,
+    pub node_version: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub added_at: String,
+    pub last_opened_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ProjectCandidate {
+    path: String,
+    name: String,
+    registered: bool,
+    info: Option<ProjectInfo>,
+    // package.json that couldn't be analyzed, e.g. invalid json
+    error: Option<AppError>,
+}
+
+#[derive(Serialize)]
+pub struct ProjectEntry {
+    #[serde(flatten)]
+    project: RegisteredProject,
+    // the folder was moved or deleted outside dev-thing
+    missing: bool,
+}
+
+// every project dev-thing knows about, persisted to `file` so the list
+// survives webview storage wipes and reinstalls
+pub struct ProjectRegistry {
+    pub file: PathBuf,
+    pub projects: Mutex<Vec<RegisteredProject>>,
+}
+
+impl ProjectRegistry {
+    pub fn load(data_dir: &Path) -> ProjectRegistry {
+        let file = data_dir.join("projects.json");
+        let projects = fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        ProjectRegistry {
+            file,
+            projects: Mutex::new(projects),
+        }
+    }
+
+    fn save(&self, projects: &[RegisteredProject]) -> Result<(), AppError> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(e, "create data directory", parent))?;
+        }
+        let content = serde_json::to_string_pretty(projects).map_err(|e| e.to_string())?;
+        fs::write(&self.file, content).map_err(|e| AppError::io(e, "save projects", &self.file))
+    }
+
+    // registers the folder, or refreshes the entry if it is already known
+    pub fn add(&self, path: &str, node_state: &NodeManager) -> Result<RegisteredProject, AppError> {
+        let path = project_path(path)?;
+        let mut projects = self.projects.lock().unwrap();
+        if let Some(existing) = projects.iter_mut().find(|project| project.path == path) {
+            refresh(existing, node_state)?;
+            let project = existing.clone();
+            self.save(&projects)?;
+            return Ok(project);
+        }
+
+        // a project moved away may have left its id behind at this path
+        let mut id = project_slug(&path);
+        let mut suffix = 1;
+        while projects.iter().any(|project| project.id == id) {
+            suffix += 1;
+            id = format!("{}-{}", project_slug(&path), suffix);
+        }
+        let mut project = RegisteredProject {
+            id,
+            name: Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(path.clone()),
+            path,
+            framework: None,
+            package_manager: None,
+            node_version: None,
+            tags: Vec::new(),
+            added_at: Local::now().to_rfc3339(),
+            last_opened_at: None,
+        };
+        refresh(&mut project, node_state)?;
+        projects.push(project.clone());
+        self.save(&projects)?;
+        Ok(project)
+    }
+
+    pub fn get(&self, id: &str) -> Result<RegisteredProject, AppError> {
+        self.projects.lock().unwrap().iter().find(|project| project.id == id).cloned().ok_or_else(|| not_registered(id))
+    }
+
+    pub fn update<R>(&self, id: &str, f: impl FnOnce(&mut RegisteredProject) -> Result<R, AppError>) -> Result<R, AppError> {
+        let mut projects = self.projects.lock().unwrap();
+        let project = projects.iter_mut().find(|project| project.id == id).ok_or_else(|| not_registered(id))?;
+        let result = f(project)?;
+        self.save(&projects)?;
+        Ok(result)
+    }
+
+    // records that the project at `path` was opened, if it is registered
+    pub fn touch(&self, path: &str) {
+        let mut projects = self.projects.lock().unwrap();
+        if let Some(project) = projects.iter_mut().find(|project| project.path == path) {
+            project.last_opened_at = Some(Local::now().to_rfc3339());
+            if let Err(e) = self.save(&projects) {
+                println!("{}", e);
+            }
+        }
+    }
+
+    pub fn remove_path(&self, path: &str) -> Result<(), AppError> {
+        let mut projects = self.projects.lock().unwrap();
+        let before = projects.len();
+        projects.retain(|project| project.path != path);
+        if projects.len() != before {
+            self.save(&projects)?;
+        }
+        Ok(())
+    }
+}
+
+fn not_registered(id: &str) -> AppError {
+    AppError::new(ErrorCode::NotFound, format!("Project {} not found", id)).with_hint("Add the project again from its folder")
+}
+
+// package.json roots under `dir`; doesn't descend into a root it found, or
+// into node_modules and hidden folders
+fn find_project_roots(dir: &Path, depth: usize, roots: &mut Vec<PathBuf>) {
+    if dir.join("package.json").is_file() {
+        roots.push(dir.to_path_buf());
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            // file_type doesn't follow symlinks, which avoids loops
+            .filter(|entry| entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false))
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name != "node_modules" && !name.starts_with('.')
+            })
+            .map(|entry| entry.path())
+            .collect(),
+        Err(_) => return,
+    };
+    entries.sort();
+    for entry in entries {
+        find_project_roots(&entry, depth - 1, roots);
+    }
+}
+
+// other commands key their state by the path the frontend passes, so it is
+// stored as given apart from a trailing separator
+fn project_path(path: &str) -> Result<String, AppError> {
+    let path = path.trim_end_matches(['/', '\\']);
+    if !Path::new(path).join("package.json").exists() {
+        return Err(AppError::new(ErrorCode::NotANodeProject, format!("{} is not a Node project (no package.json)", path))
+            .with_hint("Choose the folder that contains the project's package.json")
+            .with_context(path.to_string()));
+    }
+    Ok(path.to_string())
+}
+
+// re-reads the metadata that can change with the project's files
+fn refresh(project: &mut RegisteredProject, node_state: &NodeManager) -> Result<(), AppError> {
+    let package_json = read_package_json(&project.path)?;
+
+    project.framework = frameworks::detect(&package_json, &project.path).map(|detected| detected.detector.name().to_string());
+    project.package_manager = Some(package_managers::detect(&project.path).name);
+    project.node_version = node_version(&project.path, node_state);
+    Ok(())
+}
+
+fn node_version(path: &str, node_state: &NodeManager) -> Option<String> {
+    node_state.resolve(path).ok().and_then(|node| node.resolved).map(|install| install.version)
+}
+
+#[command]
+pub fn add_project(
+    path: String,
+    name: Option<String>,
+    tags: Option<Vec<String>>,
+    state: State<'_, ProjectRegistry>,
+    node_state: State<'_, NodeManager>,
+) -> Result<RegisteredProject, AppError> {
+    let project = state.add(&path, &node_state)?;
+    if name.is_none() && tags.is_none() {
+        return Ok(project);
+    }
+    state.update(&project.id, |project| {
+        if let Some(name) = name {
+            project.name = name;
+        }
+        if let Some(tags) = tags {
+            project.tags = tags;
+        }
+        Ok(project.clone())
+    })
+}
+
+// looks for projects under `root` (e.g. ~/code) up to `max_depth` folders
+// deep and analyzes each, for the frontend to offer registering them
+#[command]
+pub fn scan_for_projects(
+    root: String,
+    max_depth: Option<usize>,
+    state: State<'_, ProjectRegistry>,
+    node_state: State<'_, NodeManager>,
+) -> Result<Vec<ProjectCandidate>, AppError> {
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err(AppError::new(ErrorCode::NotFound, format!("{} is not a directory", root)).with_context(root));
+    }
+    let mut roots = Vec::new();
+    find_project_roots(root_path, max_depth.unwrap_or(DEFAULT_SCAN_DEPTH).min(MAX_SCAN_DEPTH), &mut roots);
+
+    let registered: Vec<String> = state.projects.lock().unwrap().iter().map(|project| project.path.clone()).collect();
+    Ok(roots
+        .into_iter()
+        .map(|root| {
+            let path = root.to_string_lossy().to_string();
+            let (info, error) = match analyze(&path, &node_state) {
+                Ok(info) => (Some(info), None),
+                Err(e) => (None, Some(e)),
+            };
+            ProjectCandidate {
+                name: root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or(path.clone()),
+                registered: registered.contains(&path),
+                path,
+                info,
+                error,
+            }
+        })
+        .collect())
+}
+
+// most recently opened first
+#[command]
+pub fn list_projects(state: State<'_, ProjectRegistry>) -> Result<Vec<ProjectEntry>, AppError> {
+    let mut projects: Vec<ProjectEntry> = state
+        .projects
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|project| ProjectEntry {
+            missing: !Path::new(&project.path).exists(),
+            project: project.clone(),
+        })
+        .collect();
+    projects.sort_by(|a, b| {
+        b.project
+            .last_opened_at
+            .cmp(&a.project.last_opened_at)
+            .then_with(|| a.project.name.to_lowercase().cmp(&b.project.name.to_lowercase()))
+    });
+    Ok(projects)
+}
+
+#[command]
+pub fn get_project(id: String, state: State<'_, ProjectRegistry>) -> Result<RegisteredProject, AppError> {
+    state.get(&id)
+}
+
+#[command]
+pub fn open_project(id: String, state: State<'_, ProjectRegistry>) -> Result<RegisteredProject, AppError> {
+    state.update(&id, |project| {
+        project.last_opened_at = Some(Local::now().to_rfc3339());
+        Ok(project.clone())
+    })
+}
+
+#[command]
+pub fn rename_project(id: String, name: String, state: State<'_, ProjectRegistry>) -> Result<RegisteredProject, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::new(ErrorCode::InvalidInput, "The project name can't be empty"));
+    }
+    state.update(&id, |project| {
+        project.name = name;
+        Ok(project.clone())
+    })
+}
+
+#[command]
+pub fn set_project_tags(id: String, tags: Vec<String>, state: State<'_, ProjectRegistry>) -> Result<RegisteredProject, AppError> {
+    let mut tags: Vec<String> = tags.into_iter().map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect();
+    tags.dedup();
+    state.update(&id, |project| {
+        project.tags = tags;
+        Ok(project.clone())
+    })
+}
+
+// points the project at its new folder after it was moved on disk, carrying
+// over the settings dev-thing keeps per project path
+#[command]
+pub fn relocate_project(
+    id: String,
+    new_path: String,
+    state: State<'_, ProjectRegistry>,
+    node_state: State<'_, NodeManager>,
+    port_state: State<'_, PortAllocator>,
+    env_state: State<'_, EnvManager>,
+    secret_state: State<'_, SecretStore>,
+) -> Result<RegisteredProject, AppError> {
+    let new_path = project_path(&new_path)?;
+    if state.projects.lock().unwrap().iter().any(|project| project.path == new_path && project.id != id) {
+        return Err(AppError::new(ErrorCode::AlreadyExists, format!("{} is already registered", new_path))
+            .with_hint("Forget the other project first")
+            .with_context(new_path));
+    }
+    let mut moved = state.get(&id)?;
+    let old_path = std::mem::replace(&mut moved.path, new_path.clone());
+    // secrets can only be moved while they are unlocked
+    secret_state.project_secrets(&old_path)?;
+    // the new folder is checked before anything moves, so a failure leaves
+    // every store on the old path
+    refresh(&mut moved, &node_state)?;
+
+    node_state.rename_project(&old_path, &new_path)?;
+    port_state.rename_project(&old_path, &new_path)?;
+    env_state.rename_project(&old_path, &new_path)?;
+    secret_state.rename_project(&old_path, &new_path)?;
+    // resolved again now that the node pin moved along
+    moved.node_version = node_version(&new_path, &node_state);
+    state.update(&id, |project| {
+        *project = moved.clone();
+        Ok(moved)
+    })
+}
+
+// removes the project from dev-thing, its files stay where they are
+#[command]
+pub fn forget_project(id: String, state: State<'_, ProjectRegistry>) -> Result<(), AppError> {
+    let mut projects = state.projects.lock().unwrap();
+    let before = projects.len();
+    projects.retain(|project| project.id != id);
+    if projects.len() == before {
+        return Err(not_registered(&id));
+    }
+    state.save(&projects)
+}
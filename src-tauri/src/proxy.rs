@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::TcpListener as StdTcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tauri::{command, State};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+use crate::certificates::CertificateStore;
+use crate::utils::project_slug;
+use crate::error::{AppError, ErrorCode};
+
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ProxyConfig {
+    pub enabled: bool,
+    pub port: u16,
+    // TLS terminated with certificates from the development CA
+    pub https: bool,
+    pub https_port: u16,
+    // browsers resolve *.localhost to loopback without touching /etc/hosts
+    pub tld: String,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        ProxyConfig {
+            enabled: true,
+            // 80 and 443 need root or a capability on most systems, users
+            // can move there from the settings once they've allowed it
+            port: 8080,
+            https: true,
+            https_port: 8443,
+            tld: "localhost".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProxyRoute {
+    pub host: String,
+    pub project: String,
+    pub port: u16,
+}
+
+// hostname -> running project
+pub type RouteTable = Arc<RwLock<HashMap<String, ProxyRoute>>>;
+
+#[derive(Serialize)]
+pub struct ProxyStatus {
+    config: ProxyConfig,
+    running: bool,
+    errors: Vec<String>,
+    routes: Vec<ProxyRouteInfo>,
+}
+
+#[derive(Serialize)]
+pub struct ProxyRouteInfo {
+    #[serde(flatten)]
+    route: ProxyRoute,
+    url: String,
+    https_url: Option<String>,
+}
+
+// reverse proxy from `<project>.<tld>` to the port of the project's dev
+// server, routes come and go as project_manager starts and stops servers
+pub struct ProxyServer {
+    pub config_file: PathBuf,
+    pub config: Mutex<ProxyConfig>,
+    pub routes: RouteTable,
+    certificates: Arc<CertificateStore>,
+    listeners: Mutex<Vec<JoinHandle<()>>>,
+    errors: Mutex<Vec<String>>,
+}
+
+impl ProxyServer {
+    pub fn load(data_dir: &Path, certificates: Arc<CertificateStore>) -> ProxyServer {
+        let config_file = data_dir.join("proxy.json");
+        let config = fs::read_to_string(&config_file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        ProxyServer {
+            config_file,
+            config: Mutex::new(config),
+            routes: Arc::new(RwLock::new(HashMap::new())),
+            certificates,
+            listeners: Mutex::new(Vec::new()),
+            errors: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn save_config(&self, config: &ProxyConfig) -> Result<(), AppError> {
+        if let Some(parent) = self.config_file.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(e, "create data directory", parent))?;
+        }
+        let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+        fs::write(&self.config_file, content).map_err(|e| AppError::io(e, "save proxy settings", &self.config_file))
+    }
+
+    // (re)binds the listeners for the current config, failures are kept for
+    // get_proxy_status instead of failing the whole app
+    pub fn start(&self) {
+        for listener in self.listeners.lock().unwrap().drain(..) {
+            listener.abort();
+        }
+        self.errors.lock().unwrap().clear();
+
+        let config = self.config.lock().unwrap().clone();
+        if !config.enabled {
+            return;
+        }
+
+        for listener in self.bind(config.port) {
+            let routes = self.routes.clone();
+            self.listeners.lock().unwrap().push(tauri::async_runtime::spawn(async move {
+                if let Err(e) = serve(listener, routes, None).await {
+                    println!("Proxy listener stopped: {}", e);
+                }
+            }));
+        }
+
+        if config.https {
+            let mut tls = ServerConfig::builder()
+                .with_safe_defaults()
+                .with_no_client_auth()
+                .with_cert_resolver(Arc::new(ProjectCertResolver {
+                    routes: self.routes.clone(),
+                    certificates: self.certificates.clone(),
+                }));
+            // connections are piped as raw bytes after the first request, so
+            // keep browsers on HTTP/1.1
+            tls.alpn_protocols = vec![b"http/1.1".to_vec()];
+            let acceptor = TlsAcceptor::from(Arc::new(tls));
+
+            for listener in self.bind(config.https_port) {
+                let routes = self.routes.clone();
+                let acceptor = acceptor.clone();
+                self.listeners.lock().unwrap().push(tauri::async_runtime::spawn(async move {
+                    if let Err(e) = serve(listener, routes, Some(acceptor)).await {
+                        println!("Proxy listener stopped: {}", e);
+                    }
+                }));
+            }
+        }
+        println!("Proxy listening on port {}", config.port);
+    }
+
+    fn bind(&self, port: u16) -> Vec<StdTcpListener> {
+        let ipv4 = match StdTcpListener::bind(("127.0.0.1", port)) {
+            Ok(listener) => listener,
+            Err(e) => {
+                let message = format!("Could not listen on port {}: {}", port, e);
+                println!("{}", message);
+                self.errors.lock().unwrap().push(message);
+                return Vec::new();
+            }
+        };
+        // browsers may try ::1 first for *.localhost, not every machine has IPv6
+        let ipv6 = StdTcpListener::bind(("::1", port)).ok();
+        std::iter::once(ipv4).chain(ipv6).collect()
+    }
+
+    pub fn add_route(&self, project: &str, port: u16) {
+        let tld = self.config.lock().unwrap().tld.clone();
+        let mut routes = self.routes.write().unwrap();
+        routes.retain(|_, route| route.project != project);
+
+        let mut host = format!("{}.{}", host_label(project), tld);
+        if routes.contains_key(&host) {
+            // two projects with the same folder name
+            host = format!("{}.{}", project_slug(project).to_lowercase().replace('_', "-"), tld);
+        }
+        routes.insert(host.clone(), ProxyRoute {
+            host,
+            project: project.to_string(),
+            port,
+        });
+    }
+
+    // only while it still points at `port`, a restarted server may own it by now
+    pub fn remove_route(&self, project: &str, port: u16) {
+        self.routes.write().unwrap().retain(|_, route| route.project != project || route.port != port);
+    }
+
+    // the routed hostname, or the one the project gets once it runs
+    pub fn host_for(&self, project: &str) -> String {
+        let routed = self
+            .routes
+            .read()
+            .unwrap()
+            .values()
+            .find(|route| route.project == project)
+            .map(|route| route.host.clone());
+        routed.unwrap_or_else(|| format!("{}.{}", host_label(project), self.config.lock().unwrap().tld))
+    }
+
+    fn route_url(&self, scheme: &str, host: &str, port: u16) -> String {
+        match (scheme, port) {
+            ("http", 80) | ("https", 443) => format!("{}://{}", scheme, host),
+            _ => format!("{}://{}:{}", scheme, host, port),
+        }
+    }
+}
+
+// picks the project's leaf certificate from the SNI hostname
+struct ProjectCertResolver {
+    routes: RouteTable,
+    certificates: Arc<CertificateStore>,
+}
+
+impl ResolvesServerCert for ProjectCertResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        let host = client_hello.server_name()?.to_lowercase();
+        let project = self.routes.read().unwrap().get(&host)?.project.clone();
+        self.certificates
+            .certified_key(&project, &host)
+            .map_err(|e| println!("No certificate for {}: {}", host, e))
+            .ok()
+    }
+}
+
+// folder name as a dns label: "My App_2" -> "my-app-2"
+fn host_label(project: &str) -> String {
+    let name = Path::new(project)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let label: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let label = label.trim_matches('-').to_string();
+    if label.is_empty() {
+        "project".to_string()
+    } else {
+        label
+    }
+}
+
+async fn serve(listener: StdTcpListener, routes: RouteTable, tls: Option<TlsAcceptor>) -> std::io::Result<()> {
+    listener.set_nonblocking(true)?;
+    let listener = TcpListener::from_std(listener)?;
+    loop {
+        let (client, _) = listener.accept().await?;
+        let routes = routes.clone();
+        let tls = tls.clone();
+        tauri::async_runtime::spawn(async move {
+            match tls {
+                Some(acceptor) => match acceptor.accept(client).await {
+                    Ok(client) => handle_connection(client, routes, "https").await,
+                    Err(e) => println!("TLS handshake failed: {}", e),
+                },
+                None => handle_connection(client, routes, "http").await,
+            }
+        });
+    }
+}
+
+// routes on the Host header of the first request and then pipes bytes both
+// ways, which also carries WebSocket upgrades (HMR) through untouched
+async fn handle_connection<S>(mut client: S, routes: RouteTable, scheme: &str)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut head = Vec::with_capacity(4096);
+    let mut buffer = [0u8; 4096];
+    while find_header_end(&head).is_none() {
+        let read = match client.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => read,
+        };
+        head.extend_from_slice(&buffer[..read]);
+        if head.len() > MAX_HEADER_BYTES {
+            respond(&mut client, "431 Request Header Fields Too Large", "Request headers are too large").await;
+            return;
+        }
+    }
+
+    let host = match request_host(&head) {
+        Some(host) => host,
+        None => {
+            respond(&mut client, "400 Bad Request", "Missing Host header").await;
+            return;
+        }
+    };
+    let route = routes.read().unwrap().get(&host).cloned();
+    let route = match route {
+        Some(route) => route,
+        None => {
+            let mut hosts: Vec<String> = routes.read().unwrap().keys().cloned().collect();
+            hosts.sort();
+            let body = if hosts.is_empty() {
+                format!("No running project for {}", host)
+            } else {
+                format!("No running project for {}. Running projects: {}", host, hosts.join(", "))
+            };
+            respond(&mut client, "404 Not Found", &body).await;
+            return;
+        }
+    };
+
+    let mut upstream = match connect_upstream(route.port).await {
+        Some(upstream) => upstream,
+        None => {
+            let body = format!("{} is not accepting connections on port {} yet", route.project, route.port);
+            respond(&mut client, "502 Bad Gateway", &body).await;
+            return;
+        }
+    };
+    // lets frameworks know the browser is on https (secure cookies, redirects)
+    if let Some(line_end) = head.windows(2).position(|window| window == b"\r\n") {
+        let header = format!("X-Forwarded-Proto: {}\r\n", scheme);
+        head.splice(line_end + 2..line_end + 2, header.into_bytes());
+    }
+    if upstream.write_all(&head).await.is_err() {
+        return;
+    }
+    let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+}
+
+// dev servers listen on 127.0.0.1, ::1 or both depending on the framework
+async fn connect_upstream(port: u16) -> Option<TcpStream> {
+    match TcpStream::connect(("127.0.0.1", port)).await {
+        Ok(stream) => Some(stream),
+        Err(_) => TcpStream::connect(("::1", port)).await.ok(),
+    }
+}
+
+fn find_header_end(head: &[u8]) -> Option<usize> {
+    head.windows(4).position(|window| window == b"\r\n\r\n")
+}
+
+fn request_host(head: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(head);
+    let value = head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("host") {
+            Some(value.trim().to_lowercase())
+        } else {
+            None
+        }
+    })?;
+
+    // strip the port, keeping bracketed IPv6 literals intact
+    let host = match value.rfind(':') {
+        Some(index) if !value[index..].contains(']') => value[..index].to_string(),
+        _ => value,
+    };
+    Some(host.trim_end_matches('.').to_string())
+}
+
+async fn respond<S: AsyncWrite + Unpin>(client: &mut S, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = client.write_all(response.as_bytes()).await;
+    let _ = client.shutdown().await;
+}
+
+#[command]
+pub fn get_proxy_status(state: State<'_, ProxyServer>) -> Result<ProxyStatus, AppError> {
+    let config = state.config.lock().unwrap().clone();
+    let errors = state.errors.lock().unwrap().clone();
+    let running = config.enabled && !state.listeners.lock().unwrap().is_empty();
+
+    let mut routes: Vec<ProxyRouteInfo> = state
+        .routes
+        .read()
+        .unwrap()
+        .values()
+        .map(|route| ProxyRouteInfo {
+            url: state.route_url("http", &route.host, config.port),
+            https_url: config.https.then(|| state.route_url("https", &route.host, config.https_port)),
+            route: route.clone(),
+        })
+        .collect();
+    routes.sort_by(|a, b| a.route.host.cmp(&b.route.host));
+
+    Ok(ProxyStatus {
+        config,
+        running,
+        errors,
+        routes,
+    })
+}
+
+#[command]
+pub fn configure_proxy(
+    enabled: Option<bool>,
+    port: Option<u16>,
+    https: Option<bool>,
+    https_port: Option<u16>,
+    tld: Option<String>,
+    state: State<'_, ProxyServer>,
+) -> Result<ProxyStatus, AppError> {
+    {
+        let mut config = state.config.lock().unwrap();
+        if let Some(enabled) = enabled {
+            config.enabled = enabled;
+        }
+        if let Some(port) = port {
+            config.port = port;
+        }
+        if let Some(https) = https {
+            config.https = https;
+        }
+        if let Some(https_port) = https_port {
+            config.https_port = https_port;
+        }
+        if let Some(tld) = tld {
+            let tld = tld.trim().trim_start_matches('.').to_lowercase();
+            if tld.is_empty() {
+                return Err(AppError::new(ErrorCode::InvalidInput, "The top level domain can't be empty"));
+            }
+            config.tld = tld;
+        }
+        state.save_config(&config)?;
+    }
+
+    // hostnames depend on the tld, so re-register the running projects
+    let routes: Vec<ProxyRoute> = state.routes.write().unwrap().drain().map(|(_, route)| route).collect();
+    for route in routes {
+        state.add_route(&route.project, route.port);
+    }
+
+    state.start();
+    get_proxy_status(state)
+}
@@ -0,0 +1,363 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, OsRng};
+use chacha20poly1305::{AeadCore, KeyInit, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use crate::env_manager::{remove_from_env_files, EnvManager};
+use crate::error::{AppError, ErrorCode};
+use crate::utils::project_slug;
+
+const BUNDLE_FORMAT: &str = "dev-thing-secrets";
+// shown instead of a secret value in output and logs
+pub const MASK: &str = "••••••";
+// shorter values would mask unrelated output ("1", "on", ...)
+const MIN_MASKED_LEN: usize = 4;
+
+type ProjectSecrets = BTreeMap<String, String>;
+
+// project secrets encrypted at rest in `file` with a key derived from the
+// master passphrase (argon2id + XChaCha20-Poly1305); the key and decrypted
+// values only live in memory while the store is unlocked
+pub struct SecretStore {
+    pub file: PathBuf,
+    unlocked: Mutex<Option<Unlocked>>,
+}
+
+struct Unlocked {
+    key: [u8; 32],
+    salt: Vec<u8>,
+    secrets: HashMap<String, ProjectSecrets>,
+}
+
+// on-disk format of the store and of exported bundles
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    format: String,
+    version: u32,
+    // slugs of projects with secrets, so a locked store can tell which
+    // projects need it without decrypting anything
+    #[serde(default)]
+    projects: Vec<String>,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Serialize)]
+pub struct SecretsStatus {
+    exists: bool,
+    unlocked: bool,
+}
+
+impl SecretStore {
+    pub fn new(data_dir: &Path) -> SecretStore {
+        SecretStore {
+            file: data_dir.join("secrets.json"),
+            unlocked: Mutex::new(None),
+        }
+    }
+
+    fn read_sealed(&self) -> Result<Option<Sealed>, AppError> {
+        match fs::read_to_string(&self.file) {
+            Ok(content) => serde_json::from_str(&content).map(Some).map_err(|e| {
+                AppError::new(ErrorCode::InvalidInput, format!("Corrupt secrets file: {}", e)).with_context(self.file.display().to_string())
+            }),
+            Err(_) => Ok(None),
+        }
+    }
+
+    // opens the store with the passphrase, creating it on first use
+    pub fn unlock(&self, passphrase: &str) -> Result<(), AppError> {
+        if passphrase.is_empty() {
+            return Err(AppError::new(ErrorCode::InvalidInput, "The passphrase can't be empty"));
+        }
+        let unlocked = match self.read_sealed()? {
+            Some(sealed) => {
+                let corrupt = |e: String| corrupt_secrets("secrets file", e, &self.file.display().to_string());
+                let salt = BASE64.decode(&sealed.salt).map_err(|e| corrupt(e.to_string()))?;
+                let key = derive_key(passphrase, &salt)?;
+                let secrets = serde_json::from_slice(&open(&sealed, &key)?).map_err(|e| corrupt(e.to_string()))?;
+                Unlocked { key, salt, secrets }
+            }
+            None => {
+                let mut salt = vec![0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                let key = derive_key(passphrase, &salt)?;
+                let unlocked = Unlocked {
+                    key,
+                    salt,
+                    secrets: HashMap::new(),
+                };
+                self.persist(&unlocked)?;
+                unlocked
+            }
+        };
+        *self.unlocked.lock().unwrap() = Some(unlocked);
+        Ok(())
+    }
+
+    pub fn lock(&self) {
+        *self.unlocked.lock().unwrap() = None;
+    }
+
+    fn persist(&self, unlocked: &Unlocked) -> Result<(), AppError> {
+        let plaintext = serde_json::to_vec(&unlocked.secrets).map_err(|e| e.to_string())?;
+        let mut projects: Vec<String> = unlocked
+            .secrets
+            .iter()
+            .filter(|(_, secrets)| !secrets.is_empty())
+            .map(|(project, _)| project_slug(project))
+            .collect();
+        projects.sort();
+        let sealed = seal(&plaintext, &unlocked.key, &unlocked.salt, projects)?;
+
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(e, "create data directory", parent))?;
+        }
+        let content = serde_json::to_string_pretty(&sealed).map_err(|e| e.to_string())?;
+        fs::write(&self.file, content).map_err(|e| AppError::io(e, "save secrets", &self.file))
+    }
+
+    fn with_unlocked<R>(&self, f: impl FnOnce(&mut Unlocked) -> Result<R, AppError>) -> Result<R, AppError> {
+        let mut unlocked = self.unlocked.lock().unwrap();
+        match unlocked.as_mut() {
+            Some(unlocked) => f(unlocked),
+            None => Err(AppError::new(ErrorCode::SecretsLocked, "Secrets are locked").with_hint("Unlock secrets with the master passphrase first")),
+        }
+    }
+
+    // values to inject when spawning the project; only fails when the
+    // project has secrets and the store is locked
+    pub fn project_secrets(&self, project: &str) -> Result<ProjectSecrets, AppError> {
+        if let Some(unlocked) = self.unlocked.lock().unwrap().as_ref() {
+            return Ok(unlocked.secrets.get(project).cloned().unwrap_or_default());
+        }
+        let has_secrets = self
+            .read_sealed()?
+            .map(|sealed| sealed.projects.contains(&project_slug(project)))
+            .unwrap_or(false);
+        if has_secrets {
+            return Err(AppError::new(ErrorCode::SecretsLocked, "This project uses secrets and they are locked")
+                .with_hint("Unlock secrets with the master passphrase first")
+                .with_context(project.to_string()));
+        }
+        Ok(ProjectSecrets::new())
+    }
+
+    // adds the project's secrets to `envs`, over its .env values, and returns
+    // the values to mask in whatever the process prints
+    pub fn inject(&self, project: &str, envs: &mut HashMap<String, String>) -> Result<Vec<String>, AppError> {
+        let secrets = self.project_secrets(project)?;
+        let masked = secrets.values().cloned().collect();
+        envs.extend(secrets);
+        Ok(masked)
+    }
+
+    pub fn set(&self, project: &str, key: &str, value: Option<String>) -> Result<(), AppError> {
+        self.with_unlocked(|unlocked| {
+            let secrets = unlocked.secrets.entry(project.to_string()).or_default();
+            match value {
+                Some(value) => {
+                    secrets.insert(key.to_string(), value);
+                }
+                None => {
+                    secrets.remove(key);
+                }
+            }
+            if secrets.is_empty() {
+                unlocked.secrets.remove(project);
+            }
+            self.persist(unlocked)
+        })
+    }
+
+    // a no-op while locked, callers check project_secrets first
+    pub fn rename_project(&self, old: &str, new: &str) -> Result<(), AppError> {
+        let mut unlocked = self.unlocked.lock().unwrap();
+        if let Some(unlocked) = unlocked.as_mut() {
+            if let Some(secrets) = unlocked.secrets.remove(old) {
+                unlocked.secrets.insert(new.to_string(), secrets);
+                self.persist(unlocked)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn corrupt_secrets(what: &str, error: String, context: &str) -> AppError {
+    AppError::new(ErrorCode::InvalidInput, format!("Corrupt {}: {}", what, error)).with_context(context)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], AppError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| AppError::new(ErrorCode::Crypto, format!("Failed to derive key: {}", e)))?;
+    Ok(key)
+}
+
+fn seal(plaintext: &[u8], key: &[u8; 32], salt: &[u8], projects: Vec<String>) -> Result<Sealed, AppError> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, plaintext).map_err(|_| AppError::new(ErrorCode::Crypto, "Failed to encrypt secrets"))?;
+    Ok(Sealed {
+        format: BUNDLE_FORMAT.to_string(),
+        version: 1,
+        projects,
+        salt: BASE64.encode(salt),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+fn open(sealed: &Sealed, key: &[u8; 32]) -> Result<Vec<u8>, AppError> {
+    let nonce = BASE64.decode(&sealed.nonce).map_err(|e| corrupt_secrets("secrets data", e.to_string(), "nonce"))?;
+    let ciphertext = BASE64.decode(&sealed.ciphertext).map_err(|e| corrupt_secrets("secrets data", e.to_string(), "ciphertext"))?;
+    if nonce.len() != 24 {
+        return Err(AppError::new(ErrorCode::InvalidInput, "Corrupt secrets data"));
+    }
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| AppError::new(ErrorCode::WrongPassphrase, "Wrong passphrase"))
+}
+
+// replaces every occurrence of a secret value in `line`
+pub fn mask_secrets(line: &str, secrets: &[String]) -> String {
+    secrets
+        .iter()
+        .filter(|secret| secret.len() >= MIN_MASKED_LEN)
+        .fold(line.to_string(), |line, secret| line.replace(secret.as_str(), MASK))
+}
+
+#[command]
+pub fn get_secrets_status(state: State<'_, SecretStore>) -> Result<SecretsStatus, AppError> {
+    Ok(SecretsStatus {
+        exists: state.file.exists(),
+        unlocked: state.unlocked.lock().unwrap().is_some(),
+    })
+}
+
+#[command]
+pub fn unlock_secrets(passphrase: String, state: State<'_, SecretStore>) -> Result<(), AppError> {
+    state.unlock(&passphrase)
+}
+
+#[command]
+pub fn lock_secrets(state: State<'_, SecretStore>) -> Result<(), AppError> {
+    state.lock();
+    Ok(())
+}
+
+// names only, values never leave the backend except at spawn time
+#[command]
+pub fn list_secrets(project_path: String, state: State<'_, SecretStore>) -> Result<Vec<String>, AppError> {
+    state.with_unlocked(|unlocked| {
+        Ok(unlocked
+            .secrets
+            .get(&project_path)
+            .map(|secrets| secrets.keys().cloned().collect())
+            .unwrap_or_default())
+    })
+}
+
+#[command]
+pub fn set_secret(project_path: String, key: String, value: String, state: State<'_, SecretStore>) -> Result<(), AppError> {
+    state.set(&project_path, &key, Some(value))
+}
+
+#[command]
+pub fn delete_secret(project_path: String, key: String, state: State<'_, SecretStore>) -> Result<(), AppError> {
+    state.set(&project_path, &key, None)
+}
+
+// moves an env value out of the project's .env files (and dev-thing
+// overrides) into the encrypted store
+#[command]
+pub fn mark_env_secret(
+    project_path: String,
+    key: String,
+    state: State<'_, SecretStore>,
+    env_state: State<'_, EnvManager>,
+) -> Result<(), AppError> {
+    let value = env_state
+        .project_env(&project_path)?
+        .remove(&key)
+        .ok_or_else(|| AppError::new(ErrorCode::NotFound, format!("{} is not set for this project", key)))?;
+    state.set(&project_path, &key, Some(value))?;
+    remove_from_env_files(&project_path, &key)?;
+    env_state.set_override(&project_path, &key, None)
+}
+
+// writes the project's secrets to `destination`, encrypted with a separate
+// passphrase to share with teammates
+#[command]
+pub fn export_secrets(
+    project_path: String,
+    destination: String,
+    passphrase: String,
+    state: State<'_, SecretStore>,
+) -> Result<String, AppError> {
+    if passphrase.is_empty() {
+        return Err(AppError::new(ErrorCode::InvalidInput, "The bundle passphrase can't be empty"));
+    }
+    let secrets = state.with_unlocked(|unlocked| Ok(unlocked.secrets.get(&project_path).cloned().unwrap_or_default()))?;
+    if secrets.is_empty() {
+        return Err(AppError::new(ErrorCode::NotFound, "This project has no secrets to export"));
+    }
+
+    let mut salt = vec![0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(&passphrase, &salt)?;
+    let plaintext = serde_json::to_vec(&secrets).map_err(|e| e.to_string())?;
+    let bundle = seal(&plaintext, &key, &salt, Vec::new())?;
+
+    let mut path = PathBuf::from(&destination);
+    if path.is_dir() {
+        path = path.join(format!("{}.secrets.json", project_slug(&project_path)));
+    }
+    let content = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| AppError::io(e, "write secrets bundle", &path))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// merges a bundle into the project's secrets, existing keys are only replaced
+// with `overwrite`; returns the imported names
+#[command]
+pub fn import_secrets(
+    project_path: String,
+    source: String,
+    passphrase: String,
+    overwrite: Option<bool>,
+    state: State<'_, SecretStore>,
+) -> Result<Vec<String>, AppError> {
+    let content = fs::read_to_string(&source).map_err(|e| AppError::io(e, "read secrets bundle", Path::new(&source)))?;
+    let not_a_bundle = || AppError::new(ErrorCode::InvalidInput, "Not a dev-thing secrets bundle").with_context(source.clone());
+    let bundle: Sealed = serde_json::from_str(&content).map_err(|_| not_a_bundle())?;
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(not_a_bundle());
+    }
+    let corrupt = |e: String| corrupt_secrets("secrets bundle", e, &source);
+    let salt = BASE64.decode(&bundle.salt).map_err(|e| corrupt(e.to_string()))?;
+    let key = derive_key(&passphrase, &salt)?;
+    let imported: ProjectSecrets = serde_json::from_slice(&open(&bundle, &key)?).map_err(|e| corrupt(e.to_string()))?;
+
+    state.with_unlocked(|unlocked| {
+        let secrets = unlocked.secrets.entry(project_path.clone()).or_default();
+        let mut names = Vec::new();
+        for (key, value) in imported {
+            if overwrite.unwrap_or(false) || !secrets.contains_key(&key) {
+                secrets.insert(key.clone(), value);
+                names.push(key);
+            }
+        }
+        state.persist(unlocked)?;
+        Ok(names)
+    })
+}
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
 use std::process::Command;
 use shlex::Shlex;
 #[cfg(target_os = "windows")]
@@ -14,27 +17,90 @@ pub fn parse_command(command: &str) -> Result<(String, Vec<String>), String> {
     Ok((executable, args))
 }
 
-pub fn execute_command(cmd: &[&str], project_path: &str) -> std::io::Result<std::process::Output> {
-    println!("Executing command: {:?}", cmd);
-    
-    let mut command = if cfg!(target_os = "windows") {
-        let mut cmd_process = Command::new("cmd");
-        cmd_process.arg("/C");
-        cmd_process.args(cmd);
+// stable, file system safe name for per-project data: the folder name plus a
+// hash of the full path so projects with the same folder name don't collide
+pub fn project_slug(project_path: &str) -> String {
+    let name: String = Path::new(project_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    // FNV-1a, std's hasher isn't guaranteed to be stable between releases
+    let hash = project_path
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    format!("{}-{:08x}", name, hash as u32)
+}
+
+// finds `program` the way a package.json script would: the project's
+// node_modules/.bin first, then PATH from `envs` (or the app's own PATH)
+pub fn resolve_executable(program: &str, project_path: &str, envs: &HashMap<String, String>) -> Result<String, String> {
+    let mut search_paths = vec![Path::new(project_path).join("node_modules").join(".bin")];
+    if let Some(path) = envs.get("PATH").cloned().or_else(|| env::var("PATH").ok()) {
+        search_paths.extend(env::split_paths(&path));
+    }
+    let search_path = env::join_paths(search_paths).map_err(|e| e.to_string())?;
+
+    which::which_in(program, Some(search_path), project_path)
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|_| format!("{} is not installed or not on PATH", program))
+}
+
+// a program and its arguments, run directly without a shell so arguments
+// (dependency names, project names) are never interpreted
+pub struct Argv {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Argv {
+    pub fn new(program: &str) -> Argv {
+        Argv {
+            program: program.to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Argv {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I: IntoIterator<Item = S>, S: Into<String>>(mut self, args: I) -> Argv {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    // runs to completion in `cwd`; the program is looked up like
+    // `resolve_executable` does, which also finds npm.cmd & co. on windows
+    pub fn output(&self, cwd: &str, envs: &HashMap<String, String>) -> std::io::Result<std::process::Output> {
+        println!("Executing command: {}", self);
+        let program = resolve_executable(&self.program, cwd, envs)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+
+        let mut command = Command::new(program);
+        command.args(&self.args);
         #[cfg(target_os = "windows")]
-        cmd_process.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        cmd_process
-    } else {
-        let mut cmd_process = Command::new("sh");
-        cmd_process.arg("-c");
-        cmd_process.arg(&cmd.join(" "));
-        cmd_process
-    };
-
-    let cmd_str = cmd.join(" ");
-    if cmd_str.contains("npm") {
-        command.env("npm_config_user_agent", "npm");
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+        if self.program == "npm" || self.program == "npx" {
+            command.env("npm_config_user_agent", "npm");
+        }
+        command.envs(envs);
+
+        command.current_dir(cwd).output()
     }
+}
 
-    command.current_dir(project_path).output()
-}
\ No newline at end of file
+impl std::fmt::Display for Argv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
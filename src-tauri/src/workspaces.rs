@@ -0,0 +1,209 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::command;
+use crate::commands::start_dev_server;
+use crate::error::{read_package_json, AppError, ErrorCode};
+use crate::frameworks::{self, FrameworkSummary};
+use crate::package_managers;
+
+// monorepo task runners that sit on top of the package manager's workspaces
+const TOOLS: [(&str, &str); 3] = [("turbo.json", "Turborepo"), ("nx.json", "Nx"), ("lerna.json", "Lerna")];
+
+#[derive(Serialize)]
+pub struct Workspace {
+    root: String,
+    manager: String,
+    tool: Option<String>,
+    packages: Vec<WorkspacePackage>,
+}
+
+#[derive(Serialize)]
+pub struct WorkspacePackage {
+    // package.json name, None for unnamed members
+    pub name: Option<String>,
+    pub path: String,
+    // relative to the workspace root, with / separators
+    pub relative_path: String,
+    pub framework: Option<FrameworkSummary>,
+    pub scripts: Vec<String>,
+    pub private: bool,
+}
+
+#[derive(Serialize)]
+pub struct WorkspaceStart {
+    package: String,
+    pid: Option<u32>,
+    error: Option<AppError>,
+}
+
+// member globs from package.json `workspaces` (a list, or yarn's
+// { packages: [...] }) or pnpm-workspace.yaml; empty when `dir` isn't a root
+pub fn workspace_patterns(dir: &Path) -> Vec<String> {
+    if let Ok(content) = fs::read_to_string(dir.join("pnpm-workspace.yaml")) {
+        return pnpm_workspace_patterns(&content);
+    }
+    let manifest: Value = match fs::read_to_string(dir.join("package.json")).ok().and_then(|content| serde_json::from_str(&content).ok()) {
+        Some(manifest) => manifest,
+        None => return Vec::new(),
+    };
+    let patterns = match &manifest["workspaces"] {
+        Value::Object(workspaces) => workspaces.get("packages").cloned().unwrap_or_default(),
+        other => other.clone(),
+    };
+    patterns
+        .as_array()
+        .map(|patterns| patterns.iter().filter_map(|pattern| pattern.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+pub fn is_workspace_root(dir: &Path) -> bool {
+    dir.join("pnpm-workspace.yaml").exists() || !workspace_patterns(dir).is_empty()
+}
+
+// just enough yaml for the `packages:` list, block or flow style
+fn pnpm_workspace_patterns(content: &str) -> Vec<String> {
+    let unquote = |item: &str| item.trim().trim_matches(|c| c == '\'' || c == '"').to_string();
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let item = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix('-'));
+        // a new top level key ends the list
+        if !line.starts_with(char::is_whitespace) && item.is_none() {
+            in_packages = false;
+            if let Some(rest) = trimmed.strip_prefix("packages:") {
+                let rest = rest.trim();
+                match rest.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                    Some(flow) => patterns.extend(flow.split(',').map(unquote).filter(|item| !item.is_empty())),
+                    None => in_packages = true,
+                }
+            }
+            continue;
+        }
+        if let (true, Some(item)) = (in_packages, item) {
+            let item = item.split(" #").next().unwrap_or(item);
+            patterns.push(unquote(item));
+        }
+    }
+    patterns
+}
+
+// folders matching the patterns that have a package.json; `!` patterns exclude
+fn member_dirs(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    let (excludes, includes): (Vec<&String>, Vec<&String>) = patterns.iter().partition(|pattern| pattern.starts_with('!'));
+    let excludes: Vec<glob::Pattern> = excludes
+        .iter()
+        .filter_map(|pattern| glob::Pattern::new(pattern.trim_start_matches('!').trim_start_matches("./")).ok())
+        .collect();
+
+    let mut dirs = Vec::new();
+    for pattern in includes {
+        // the root itself may contain characters glob treats as special
+        let full = Path::new(&glob::Pattern::escape(&root.to_string_lossy())).join(pattern.trim_start_matches("./"));
+        let matches = match glob::glob(&full.to_string_lossy()) {
+            Ok(matches) => matches,
+            Err(e) => {
+                println!("Skipping workspace pattern {}: {}", pattern, e);
+                continue;
+            }
+        };
+        for dir in matches.flatten() {
+            let relative = relative_path(root, &dir);
+            let excluded = relative.split('/').any(|part| part == "node_modules") || excludes.iter().any(|exclude| exclude.matches(&relative));
+            if !excluded && dir.join("package.json").is_file() && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs.sort();
+    dirs
+}
+
+fn relative_path(root: &Path, dir: &Path) -> String {
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    relative.components().map(|part| part.as_os_str().to_string_lossy().to_string()).collect::<Vec<_>>().join("/")
+}
+
+// the workspace `project_path` is the root of, or is a member of
+pub fn find_workspace_root(project_path: &str) -> Option<PathBuf> {
+    let project = Path::new(project_path);
+    project.ancestors().find(|dir| is_workspace_root(dir)).map(Path::to_path_buf).filter(|root| {
+        root == project || member_dirs(root, &workspace_patterns(root)).iter().any(|member| member == project)
+    })
+}
+
+pub fn load_workspace(root: &Path) -> Result<Workspace, AppError> {
+    let root_path = root.to_string_lossy().to_string();
+    let packages = member_dirs(root, &workspace_patterns(root))
+        .into_iter()
+        .filter_map(|dir| {
+            let path = dir.to_string_lossy().to_string();
+            let manifest = read_package_json(&path).map_err(|e| println!("Skipping workspace package {}: {}", path, e)).ok()?;
+            let scripts = manifest["scripts"].as_object().map(|scripts| scripts.keys().cloned().collect()).unwrap_or_default();
+            Some(WorkspacePackage {
+                name: manifest["name"].as_str().map(str::to_string),
+                relative_path: relative_path(root, &dir),
+                framework: frameworks::detect(&manifest, &path).as_ref().map(FrameworkSummary::from),
+                scripts,
+                private: manifest["private"].as_bool().unwrap_or(false),
+                path,
+            })
+        })
+        .collect();
+
+    Ok(Workspace {
+        manager: package_managers::detect(&root_path).name,
+        tool: TOOLS.iter().find(|(file, _)| root.join(file).exists()).map(|(_, tool)| tool.to_string()),
+        root: root_path,
+        packages,
+    })
+}
+
+// a member of the workspace by package name or path relative to the root
+pub fn find_member(project_path: &str, package: &str) -> Result<WorkspacePackage, AppError> {
+    let root = find_workspace_root(project_path).ok_or_else(|| {
+        AppError::new(ErrorCode::InvalidInput, "The project is not a workspace").with_context(project_path.to_string())
+    })?;
+    let package = package.trim_start_matches("./").trim_end_matches('/');
+    load_workspace(&root)?
+        .packages
+        .into_iter()
+        .find(|member| member.name.as_deref() == Some(package) || member.relative_path == package)
+        .ok_or_else(|| {
+            AppError::new(ErrorCode::NotFound, format!("No workspace package named {}", package))
+                .with_context(root.to_string_lossy().to_string())
+        })
+}
+
+// the workspace the project belongs to, None for single package projects
+#[command]
+pub fn get_workspace(project_path: String) -> Result<Option<Workspace>, AppError> {
+    match find_workspace_root(&project_path) {
+        Some(root) => load_workspace(&root).map(Some),
+        None => Ok(None),
+    }
+}
+
+// starts the dev server of each of the given apps (names or relative
+// paths); one failing doesn't stop the others
+#[command]
+pub fn start_workspace_apps(project_path: String, packages: Vec<String>, window: tauri::Window) -> Result<Vec<WorkspaceStart>, AppError> {
+    let mut results = Vec::new();
+    for package in packages {
+        let started = find_member(&project_path, &package).and_then(|member| start_dev_server(&member.path, &window));
+        let (pid, error) = match started {
+            Ok(pid) => (Some(pid), None),
+            Err(e) => {
+                println!("Failed to start {}: {}", package, e);
+                (None, Some(e))
+            }
+        };
+        results.push(WorkspaceStart { package, pid, error });
+    }
+    Ok(results)
+}